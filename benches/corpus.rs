@@ -0,0 +1,96 @@
+//! Documents a generated corpus of TypeScript files with one thread and with one per core
+//!
+//! `cargo bench --bench corpus`, set `CORPUS_FILES` to change the number of files (2000 by default)
+//! and `CORPUS_JOBS` to the thread counts to compare (`1,4,8`).
+
+use auto_js_doc::{default_jobs, map_files, Documenter, Options};
+use std::fs;
+use std::time::Instant;
+
+// A file of a few hundred lines with functions, classes and the types they use
+fn generate(index: usize) -> String {
+    let mut source_code = format!(
+        "import {{ Logger }} from './logger';\n\ninterface Options{0} {{\n  // How many times to retry\n  retries: number;\n  timeout?: number;\n}}\n\n",
+        index
+    );
+    for function in 0..20 {
+        source_code.push_str(&format!(
+            "// Loads item {1}\nexport async function loadItem{0}_{1}(id: string, options: Options{0}, onDone: (error: Error) => void = () => {{}}): Promise<string[]> {{\n  if (!id) {{\n    throw new Error('missing id');\n  }}\n  return [id];\n}}\n\n",
+            index, function
+        ));
+    }
+    source_code.push_str(&format!("export class Service{} {{\n", index));
+    for method in 0..20 {
+        source_code.push_str(&format!(
+            "  /**\n   * Old\n   */\n  public getUserById{0}(id: number, name?: string): string | null {{\n    return name ?? null;\n  }}\n\n  private *items{0}(): Generator<number> {{\n    yield {0};\n  }}\n\n",
+            method
+        ));
+    }
+    source_code.push_str("}\n");
+    source_code
+}
+
+fn main() {
+    let count = std::env::var("CORPUS_FILES")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(2000);
+
+    let dir = std::env::temp_dir().join("auto-js-doc-corpus");
+    fs::remove_dir_all(&dir).ok();
+    let mut paths = Vec::new();
+    for index in 0..count {
+        let path = dir.join(format!("{}/file{}.ts", index % 50, index));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, generate(index)).unwrap();
+        paths.push(path);
+    }
+    let bytes = paths
+        .iter()
+        .map(|path| fs::metadata(path).unwrap().len())
+        .sum::<u64>();
+    println!(
+        "{} files, {:.1} MB",
+        paths.len(),
+        bytes as f64 / 1024.0 / 1024.0
+    );
+
+    let jobs = std::env::var("CORPUS_JOBS")
+        .ok()
+        .and_then(|jobs| jobs.split(',').map(|jobs| jobs.parse().ok()).collect())
+        .unwrap_or_else(|| vec![1, default_jobs()]);
+
+    let mut first_edits = None;
+    for jobs in jobs {
+        let files = paths
+            .iter()
+            .map(|path| (path.clone(), Options::default()))
+            .collect();
+        let start = Instant::now();
+        let results = map_files(files, jobs, |_, source_code, options| {
+            Documenter::new()
+                .options(options.clone())
+                .edits(&source_code)
+        });
+        let elapsed = start.elapsed();
+
+        let edits = results
+            .into_iter()
+            .map(|result| result.unwrap())
+            .collect::<Vec<_>>();
+        let functions = edits.iter().map(|edits| edits.len()).sum::<usize>();
+        println!(
+            "{:>3} jobs: {:>8.1?} ({} edits, {:.0} files/s)",
+            jobs,
+            elapsed,
+            functions,
+            paths.len() as f64 / elapsed.as_secs_f64()
+        );
+
+        // The same output in the same order, whatever the number of threads
+        let first_edits = first_edits.get_or_insert_with(|| edits.clone());
+        assert!(*first_edits == edits, "output differs with {} jobs", jobs);
+    }
+
+    fs::remove_dir_all(&dir).ok();
+}
@@ -1,2 +1,11 @@
 #!/bin/bash
+set -e
 
+# The binary, which also serves the site
+cargo build --release
+
+# The bindings for the site to run the documenter in the browser (see WebAssembly in the readme)
+wasm-pack build --target web --out-dir site/pkg --release -- --features wasm
+
+# The npm package for node, e.g. for build scripts and lint plugins
+wasm-pack build --target nodejs --out-dir pkg --release -- --features wasm
@@ -0,0 +1,330 @@
+use std::ops::Range;
+
+use crate::types::{format_type, TypeStyle};
+
+// The tags written by the generator, the others in an existing block are kept as they are
+const GENERATED_TAGS: [&str; 9] = [
+    "@param",
+    "@arg",
+    "@argument",
+    "@returns",
+    "@return",
+    "@generator",
+    "@yields",
+    "@yield",
+    "@callback",
+];
+
+// A `@param` tag of a JsDoc block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DocParam {
+    pub(crate) name: String,
+    pub(crate) param_type: Option<String>,
+    // Written in brackets, `[name]` or `[name=default]`
+    pub(crate) optional: bool,
+    pub(crate) default: Option<String>,
+    // After the name and any `-`, with the lines that follow the tag
+    pub(crate) description: String,
+    // The tag in the source, from the `@` to the end of its line
+    pub(crate) range: Range<usize>,
+}
+
+// The `@returns` tag of a JsDoc block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DocReturns {
+    pub(crate) return_type: Option<String>,
+    pub(crate) description: String,
+    pub(crate) range: Range<usize>,
+}
+
+// An existing JsDoc block
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DocBlock {
+    // The lines before the first tag
+    pub(crate) description: Vec<String>,
+    pub(crate) params: Vec<DocParam>,
+    pub(crate) returns: Option<DocReturns>,
+    // Tags the generator doesn't write, like `@example`, each as its lines without the leading ` * `
+    pub(crate) other_tags: Vec<Vec<String>>,
+}
+
+// The tag the lines after it belong to
+enum Section {
+    Description,
+    Param(usize),
+    Returns,
+    Other(usize),
+    Generated,
+}
+
+// Read the block in the range of the source, a tag's type and name are read from its first line
+pub(crate) fn parse_block(source_code: &str, range: Range<usize>) -> DocBlock {
+    let mut block = DocBlock::default();
+    let mut section = Section::Description;
+    let mut offset = range.start;
+    for line in source_code[range].split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        // Keep the indentation after the `*` for code in `@example`
+        let text = line
+            .trim_end()
+            .trim_end_matches("*/")
+            .trim_end()
+            .trim_start()
+            .trim_start_matches("/**")
+            .trim_start_matches('*');
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        let content = text.trim_start();
+        if !content.starts_with('@') {
+            match section {
+                Section::Description => block.description.push(content.to_owned()),
+                Section::Param(i) => add_line(&mut block.params[i].description, content),
+                Section::Returns => {
+                    if let Some(returns) = &mut block.returns {
+                        add_line(&mut returns.description, content);
+                    }
+                }
+                Section::Other(i) => block.other_tags[i].push(text.to_owned()),
+                Section::Generated => {}
+            }
+            continue;
+        }
+        let start = line_start + line.find(content).unwrap();
+        let range = start..start + content.len();
+
+        let (tag, rest) = content
+            .split_once(char::is_whitespace)
+            .unwrap_or((content, ""));
+        section = match tag {
+            "@param" | "@arg" | "@argument" => match parse_param(rest, range) {
+                Some(param) => {
+                    block.params.push(param);
+                    Section::Param(block.params.len() - 1)
+                }
+                None => Section::Generated,
+            },
+            "@returns" | "@return" => {
+                let (return_type, rest) = take_type(rest.trim_start());
+                block.returns = Some(DocReturns {
+                    return_type,
+                    description: describe(rest),
+                    range,
+                });
+                Section::Returns
+            }
+            _ if GENERATED_TAGS.contains(&tag) => Section::Generated,
+            _ => {
+                block.other_tags.push(vec![content.to_owned()]);
+                Section::Other(block.other_tags.len() - 1)
+            }
+        };
+    }
+
+    trim_blank_lines(&mut block.description);
+    for tag in &mut block.other_tags {
+        trim_blank_lines(tag);
+    }
+    block
+}
+
+// Continue a description on the next line
+fn add_line(description: &mut String, line: &str) {
+    if line.is_empty() {
+        return;
+    }
+    if !description.is_empty() {
+        description.push('\n');
+    }
+    description.push_str(line);
+}
+
+fn trim_blank_lines(lines: &mut Vec<String>) {
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    let leading = lines
+        .iter()
+        .take_while(|line| line.trim().is_empty())
+        .count();
+    lines.drain(..leading);
+}
+
+// The description after a tag's name, without the `-` separating them
+fn describe(rest: &str) -> String {
+    let rest = rest.trim();
+    rest.strip_prefix('-').unwrap_or(rest).trim().to_owned()
+}
+
+// `{type} name`, `name {type}`, `{type} [name=default]` and the like
+fn parse_param(text: &str, range: Range<usize>) -> Option<DocParam> {
+    let (mut param_type, rest) = take_type(text.trim_start());
+    let rest = rest.trim_start();
+
+    let (name, mut rest) = if rest.starts_with('[') {
+        let end = closing(rest, '[', ']')?;
+        (&rest[..=end], &rest[end + 1..])
+    } else {
+        rest.split_once(char::is_whitespace).unwrap_or((rest, ""))
+    };
+    if param_type.is_none() {
+        (param_type, rest) = take_type(rest.trim_start());
+    }
+
+    let optional = name.starts_with('[') && name.ends_with(']');
+    let name = match optional {
+        true => &name[1..name.len() - 1],
+        false => name,
+    };
+    let (name, default) = match name.split_once('=') {
+        Some((name, default)) => (name, Some(default.trim().to_owned())),
+        None => (name, None),
+    };
+    let name = name.trim().trim_start_matches("...");
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(DocParam {
+        name: name.to_owned(),
+        param_type,
+        optional,
+        default,
+        description: describe(rest),
+        range,
+    })
+}
+
+// Split off a leading `{type}`, allowing for braces inside it
+fn take_type(text: &str) -> (Option<String>, &str) {
+    if !text.starts_with('{') {
+        return (None, text);
+    }
+    match closing(text, '{', '}') {
+        Some(end) => (Some(text[1..end].trim().to_owned()), &text[end + 1..]),
+        None => (None, text),
+    }
+}
+
+// The index of the bracket closing the one the text starts with, skipping quoted strings
+fn closing(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, c) if c == open => depth += 1,
+            (None, c) if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Checks if the existing block in the range has the tags of the generated one: the same params in the
+// same order, with the same types and brackets, and the same `@returns`. Descriptions and the tags
+// that aren't generated don't matter
+pub(crate) fn is_up_to_date(source_code: &str, doc: &Range<usize>, generated: &str) -> bool {
+    let existing = parse_block(source_code, doc.clone());
+    // Any `@callback` blocks come first
+    let generated = &generated[generated.rfind("/**").unwrap_or(0)..];
+    let generated = parse_block(generated, 0..generated.len());
+
+    let same_type = |a: &Option<String>, b: &Option<String>| {
+        a.as_deref().map(|a| format_type(a, TypeStyle::Typescript))
+            == b.as_deref().map(|b| format_type(b, TypeStyle::Typescript))
+    };
+    existing.params.len() == generated.params.len()
+        && existing
+            .params
+            .iter()
+            .zip(&generated.params)
+            .all(|(existing, generated)| {
+                existing.name == generated.name
+                    && same_type(&existing.param_type, &generated.param_type)
+                    // Params with a default can be written either way
+                    && (existing.optional == generated.optional || generated.default.is_some())
+            })
+        && match (&existing.returns, &generated.returns) {
+            (Some(existing), Some(generated)) => {
+                same_type(&existing.return_type, &generated.return_type)
+            }
+            (None, None) => true,
+            _ => false,
+        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block() {
+        let source_code = "/**\n * a\n * @param {{ a: string }} [b={}] - b\n * @param c {number}\n * @arg ...d\n * @returns {string} */";
+        let block = parse_block(source_code, 0..source_code.len());
+        assert_eq!(block.description, ["a"]);
+        assert_eq!(
+            block.params,
+            vec![
+                DocParam {
+                    name: "b".to_owned(),
+                    param_type: Some("{ a: string }".to_owned()),
+                    optional: true,
+                    default: Some("{}".to_owned()),
+                    description: "b".to_owned(),
+                    range: 12..45,
+                },
+                DocParam {
+                    name: "c".to_owned(),
+                    param_type: Some("number".to_owned()),
+                    optional: false,
+                    default: None,
+                    description: String::new(),
+                    range: 49..66,
+                },
+                DocParam {
+                    name: "d".to_owned(),
+                    param_type: None,
+                    optional: false,
+                    default: None,
+                    description: String::new(),
+                    range: 70..79,
+                },
+            ]
+        );
+        assert_eq!(
+            block.returns,
+            Some(DocReturns {
+                return_type: Some("string".to_owned()),
+                description: String::new(),
+                range: 83..100,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_block_descriptions() {
+        let source_code = "/**\n * Finds a user.\n *\n * Looks in the cache first.\n *\n * @param {string} id - The id,\n *   as stored\n * @returns {User} The user\n * @example\n *   find(\"a\");\n *\n * @deprecated\n * @generator\n */";
+        let block = parse_block(source_code, 0..source_code.len());
+        assert_eq!(
+            block.description,
+            ["Finds a user.", "", "Looks in the cache first."]
+        );
+        assert_eq!(block.params[0].description, "The id,\nas stored");
+        assert_eq!(block.returns.unwrap().description, "The user");
+        assert_eq!(
+            block.other_tags,
+            [
+                vec!["@example".to_owned(), "  find(\"a\");".to_owned()],
+                vec!["@deprecated".to_owned()],
+            ]
+        );
+    }
+}
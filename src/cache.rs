@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use auto_js_doc::Options;
+
+// The file in the cache directory holding the entries
+const CACHE_FILE: &str = "files.json";
+
+/// The files that were up to date after the last run, so unchanged ones can be skipped on the next
+///
+/// Each path maps to a hash of the tool version, the options and the file's contents, so changing any
+/// of them means the file gets documented again.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    options: u64,
+    entries: CacheFile,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheFile {
+    files: BTreeMap<String, String>,
+}
+
+impl Cache {
+    /// Load the cache in the directory for the options, starting empty if there isn't one yet
+    pub fn open(dir: &Path, options: &Options) -> Cache {
+        let entries = fs::read_to_string(dir.join(CACHE_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Cache {
+            dir: dir.to_owned(),
+            options: options_hash(options),
+            entries,
+        }
+    }
+
+    /// Checks if the file had these contents the last time it was up to date with the same options
+    pub fn is_up_to_date(&self, path: &Path, source_code: &str) -> bool {
+        self.entries.files.get(&key(path)) == Some(&self.hash(source_code))
+    }
+
+    /// Record that the file is up to date with these contents
+    pub fn insert(&mut self, path: &Path, source_code: &str) {
+        let hash = self.hash(source_code);
+        self.entries.files.insert(key(path), hash);
+    }
+
+    /// Write the cache back to its directory, which is kept out of git
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(".gitignore"), "*\n")?;
+        fs::write(
+            self.dir.join(CACHE_FILE),
+            serde_json::to_string_pretty(&self.entries)?,
+        )
+    }
+
+    fn hash(&self, source_code: &str) -> String {
+        let mut hash = Fnv::default();
+        hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+        hash.write(&self.options.to_le_bytes());
+        hash.write(source_code.as_bytes());
+        format!("{:016x}", hash.0)
+    }
+}
+
+fn key(path: &Path) -> String {
+    path.display().to_string()
+}
+
+// The options that change what gets documented, leaving out the path of the file they were given for
+fn options_hash(options: &Options) -> u64 {
+    let mut options = options.clone();
+    options.source_path = None;
+    let mut hash = Fnv::default();
+    hash.write(format!("{:?}", options).as_bytes());
+    hash.0
+}
+
+// 64 bit FNV-1a, the std hasher isn't guaranteed to give the same hashes between releases
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Fnv {
+        Fnv(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache() {
+        let dir = std::env::temp_dir().join(format!("auto-js-doc-cache-{}", std::process::id()));
+        let options = Options::default();
+        let path = Path::new("src/a.ts");
+
+        let mut cache = Cache::open(&dir, &options);
+        assert!(!cache.is_up_to_date(path, "function a() {}"));
+        cache.insert(path, "function a() {}");
+        cache.save().unwrap();
+
+        let cache = Cache::open(&dir, &options);
+        assert!(cache.is_up_to_date(path, "function a() {}"));
+        assert!(!cache.is_up_to_date(path, "function b() {}"));
+        assert!(!cache.is_up_to_date(Path::new("src/b.ts"), "function a() {}"));
+
+        // Only the options that change the output matter
+        let mut options = Options::default();
+        options.source_path = Some(path.to_owned());
+        let cache = Cache::open(&dir, &options);
+        assert!(cache.is_up_to_date(path, "function a() {}"));
+        options.exported_only = true;
+        let cache = Cache::open(&dir, &options);
+        let up_to_date = cache.is_up_to_date(path, "function a() {}");
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!up_to_date);
+    }
+}
@@ -0,0 +1,412 @@
+use auto_js_doc::{DescriptionStyle, Options, Position, ReportFormat, TypeReferences, TypeStyle};
+use regex::Regex;
+use std::net::IpAddr;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// The directory the cache is kept in with `--cache`
+pub const DEFAULT_CACHE_DIR: &str = ".auto-js-doc-cache";
+
+/// What the command line prints
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// The source with the JsDoc blocks added
+    #[default]
+    Source,
+    /// The edits adding the JsDoc blocks as JSON
+    Edits,
+}
+
+/// A position in the source to document the function at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    /// One based line and column
+    LineColumn { line: usize, column: usize },
+    /// Byte offset
+    Offset(usize),
+}
+
+impl Cursor {
+    /// The byte offset of the cursor in the source
+    pub fn offset(&self, source_code: &str) -> usize {
+        match *self {
+            Cursor::LineColumn { line, column } => Position {
+                line: line.saturating_sub(1),
+                column: column.saturating_sub(1),
+            }
+            .offset(source_code),
+            Cursor::Offset(offset) => offset.min(source_code.len()),
+        }
+    }
+}
+
+/// The command line arguments, the options for the documenter and the settings of the command itself
+#[derive(Debug, Default, Clone)]
+pub struct Cli {
+    /// How the declarations are documented
+    pub options: Options,
+    /// What the command line prints
+    pub output: Output,
+    /// Report the missing and outdated JsDoc blocks instead of adding them
+    pub check: bool,
+    /// Report the problems with the existing JsDoc blocks, like `@param` tags for parameters that
+    /// aren't there, instead of adding blocks
+    pub validate: bool,
+    /// How `check` reports the diagnostics and `coverage` the coverage
+    pub format: ReportFormat,
+    /// Fail `coverage` when less than this percentage of declarations is documented
+    pub min_coverage: Option<f64>,
+    /// Fail `coverage` when less than this percentage of exported functions and classes is documented
+    pub min_exported_coverage: Option<f64>,
+    /// Only document the function at this position
+    pub at: Option<Cursor>,
+    /// Only document declarations touching these one based line ranges, all of them when empty
+    pub line_ranges: Vec<RangeInclusive<usize>>,
+    /// Read a `git diff --unified=0` from stdin and document the changed declarations of each file in it
+    pub git_diff: bool,
+    /// Files and directories to document in place of stdin
+    pub paths: Vec<PathBuf>,
+    /// How many files to document at once with `paths` or `git_diff`, one per core by default
+    pub jobs: Option<usize>,
+    /// Where to remember the files that are up to date, to skip them when they haven't changed
+    pub cache_dir: Option<PathBuf>,
+    /// The address `serve` listens on, localhost by default
+    pub bind: Option<IpAddr>,
+    /// The port `serve` listens on
+    pub port: Option<u16>,
+    /// The `Host` headers `serve` answers to, only localhost when empty
+    pub allowed_hosts: Vec<String>,
+    /// The largest request body `serve` accepts, in bytes
+    pub max_body_size: Option<usize>,
+    /// Where `serve` finds the `pkg` directory built by `wasm-pack`
+    pub site_dir: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Read the command line arguments (excluding the program name)
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Cli, String> {
+        let mut cli = Cli::default();
+        let options = &mut cli.options;
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--exported-only" => options.exported_only = true,
+                "--public-only" => options.public_only = true,
+                "--skip-constructors" => options.skip_constructors = true,
+                "--min-lines" => {
+                    let value = args.next().ok_or("--min-lines expects a value")?;
+                    let lines = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid --min-lines value: {}", value))?;
+                    options.min_lines = Some(lines);
+                }
+                "--ignore-pattern" => {
+                    let value = args.next().ok_or("--ignore-pattern expects a value")?;
+                    let pattern = Regex::new(&value)
+                        .map_err(|e| format!("invalid --ignore-pattern: {}", e))?;
+                    options.ignore_pattern = Some(pattern);
+                }
+                "--strip-param-comments" => options.strip_param_comments = true,
+                "--infer-returns" => options.infer_returns = true,
+                "--skip-void-returns" => options.skip_void_returns = true,
+                "--infer-throws" => options.infer_throws = true,
+                "--callback-typedefs" => options.callback_typedefs = true,
+                "--description" => {
+                    let value = args.next().ok_or("--description expects a value")?;
+                    options.description = match value.as_str() {
+                        "name" => DescriptionStyle::Name,
+                        "humanize" => DescriptionStyle::Humanize,
+                        "todo" => DescriptionStyle::Todo,
+                        _ => return Err(format!("invalid --description value: {}", value)),
+                    };
+                }
+                "--type-style" => {
+                    let value = args.next().ok_or("--type-style expects a value")?;
+                    options.type_style = match value.as_str() {
+                        "typescript" => TypeStyle::Typescript,
+                        "closure" => TypeStyle::Closure,
+                        "jsdoc-strict" => TypeStyle::JsdocStrict,
+                        _ => return Err(format!("invalid --type-style value: {}", value)),
+                    };
+                }
+                "--type-references" => {
+                    let value = args.next().ok_or("--type-references expects a value")?;
+                    options.type_references = match value.as_str() {
+                        "keep" => TypeReferences::Keep,
+                        "inline" => TypeReferences::Inline,
+                        "see" => TypeReferences::See,
+                        "import" => TypeReferences::Import,
+                        _ => return Err(format!("invalid --type-references value: {}", value)),
+                    };
+                }
+                "--source-path" => {
+                    let value = args.next().ok_or("--source-path expects a value")?;
+                    options.source_path = Some(PathBuf::from(value));
+                }
+                "--follow-imports" => options.follow_imports = true,
+                "--verb-rule" => {
+                    let value = args.next().ok_or("--verb-rule expects a value")?;
+                    let (verb, template) = value.split_once('=').ok_or(format!(
+                        "invalid --verb-rule, expected verb=template: {}",
+                        value
+                    ))?;
+                    options
+                        .verb_rules
+                        .push((verb.to_owned(), template.to_owned()));
+                }
+                "--output" => {
+                    let value = args.next().ok_or("--output expects a value")?;
+                    cli.output = match value.as_str() {
+                        "source" => Output::Source,
+                        "edits" => Output::Edits,
+                        _ => return Err(format!("invalid --output value: {}", value)),
+                    };
+                }
+                "--check" => cli.check = true,
+                "--validate" => cli.validate = true,
+                "--format" => {
+                    let value = args.next().ok_or("--format expects a value")?;
+                    cli.format = match value.as_str() {
+                        "text" => ReportFormat::Text,
+                        "eslint" => ReportFormat::Eslint,
+                        "sarif" => ReportFormat::Sarif,
+                        "json" => ReportFormat::Json,
+                        "cobertura" => ReportFormat::Cobertura,
+                        _ => return Err(format!("invalid --format value: {}", value)),
+                    };
+                }
+                "--at" => {
+                    let value = args.next().ok_or("--at expects a value")?;
+                    let (line, column) = value
+                        .split_once(':')
+                        .and_then(|(line, column)| Some((line.parse().ok()?, column.parse().ok()?)))
+                        .ok_or(format!("invalid --at, expected LINE:COL: {}", value))?;
+                    cli.at = Some(Cursor::LineColumn { line, column });
+                }
+                "--offset" => {
+                    let value = args.next().ok_or("--offset expects a value")?;
+                    let offset = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid --offset value: {}", value))?;
+                    cli.at = Some(Cursor::Offset(offset));
+                }
+                "--lines" => {
+                    let value = args.next().ok_or("--lines expects a value")?;
+                    let parse = |line: &str| line.trim().parse::<usize>().ok();
+                    let range = match value.split_once('-') {
+                        Some((start, end)) => parse(start).zip(parse(end)),
+                        None => parse(&value).map(|line| (line, line)),
+                    }
+                    .filter(|(start, end)| start <= end)
+                    .ok_or(format!("invalid --lines, expected START-END: {}", value))?;
+                    cli.line_ranges.push(range.0..=range.1);
+                }
+                "--git-diff" => cli.git_diff = true,
+                "--jobs" => {
+                    let value = args.next().ok_or("--jobs expects a value")?;
+                    let jobs = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|jobs| *jobs > 0)
+                        .ok_or(format!("invalid --jobs value: {}", value))?;
+                    cli.jobs = Some(jobs);
+                }
+                "--min-coverage" => {
+                    let value = args.next().ok_or("--min-coverage expects a value")?;
+                    cli.min_coverage = Some(percentage(&value, "--min-coverage")?);
+                }
+                "--min-exported-coverage" => {
+                    let value = args
+                        .next()
+                        .ok_or("--min-exported-coverage expects a value")?;
+                    cli.min_exported_coverage =
+                        Some(percentage(&value, "--min-exported-coverage")?);
+                }
+                "--cache" => {
+                    cli.cache_dir
+                        .get_or_insert_with(|| PathBuf::from(DEFAULT_CACHE_DIR));
+                }
+                "--cache-dir" => {
+                    let value = args.next().ok_or("--cache-dir expects a value")?;
+                    cli.cache_dir = Some(PathBuf::from(value));
+                }
+                "--port" => {
+                    let value = args.next().ok_or("--port expects a value")?;
+                    let port = value
+                        .parse::<u16>()
+                        .map_err(|_| format!("invalid --port value: {}", value))?;
+                    cli.port = Some(port);
+                }
+                "--bind" => {
+                    let value = args.next().ok_or("--bind expects a value")?;
+                    let address = value
+                        .parse::<IpAddr>()
+                        .map_err(|_| format!("invalid --bind value: {}", value))?;
+                    cli.bind = Some(address);
+                }
+                "--allowed-host" => {
+                    let value = args.next().ok_or("--allowed-host expects a value")?;
+                    cli.allowed_hosts.push(value);
+                }
+                "--max-body-size" => {
+                    let value = args.next().ok_or("--max-body-size expects a value")?;
+                    let size = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid --max-body-size value: {}", value))?;
+                    cli.max_body_size = Some(size);
+                }
+                "--site-dir" => {
+                    let value = args.next().ok_or("--site-dir expects a value")?;
+                    cli.site_dir = Some(PathBuf::from(value));
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown argument: {}", arg)),
+                _ => cli.paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+// Parse a percentage from 0 to 100 given to the flag
+fn percentage(value: &str, flag: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|percentage| (0.0..=100.0).contains(percentage))
+        .ok_or(format!("invalid {} value: {}", flag, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_args() {
+        let args = [
+            "--exported-only",
+            "--public-only",
+            "--skip-constructors",
+            "--min-lines",
+            "3",
+            "--ignore-pattern",
+            "^_",
+            "--description",
+            "humanize",
+            "--verb-rule",
+            "fetch=Retrieves the {}.",
+            "--strip-param-comments",
+            "--infer-returns",
+            "--skip-void-returns",
+            "--infer-throws",
+            "--callback-typedefs",
+            "--type-style",
+            "closure",
+            "--type-references",
+            "inline",
+            "--source-path",
+            "src/user.ts",
+            "--follow-imports",
+            "--output",
+            "edits",
+            "--check",
+            "--validate",
+            "--format",
+            "sarif",
+            "--at",
+            "3:14",
+            "--lines",
+            "3-10",
+            "--lines",
+            "12",
+            "--git-diff",
+            "--jobs",
+            "4",
+            "--cache-dir",
+            "target/cache",
+            "--min-coverage",
+            "80",
+            "--min-exported-coverage",
+            "99.5",
+            "--bind",
+            "0.0.0.0",
+            "--port",
+            "8080",
+            "--allowed-host",
+            "localhost:8080",
+            "--max-body-size",
+            "1024",
+            "--site-dir",
+            "public",
+            "src",
+            "lib/a.ts",
+        ];
+        let cli = Cli::from_args(args.iter().map(|a| a.to_string())).unwrap();
+        let options = &cli.options;
+
+        assert!(options.exported_only);
+        assert!(options.public_only);
+        assert!(options.skip_constructors);
+        assert_eq!(options.min_lines, Some(3));
+        assert!(options.ignore_pattern.as_ref().unwrap().is_match("_helper"));
+        assert_eq!(options.description, DescriptionStyle::Humanize);
+        assert!(options.strip_param_comments);
+        assert!(options.infer_returns);
+        assert!(options.skip_void_returns);
+        assert!(options.infer_throws);
+        assert!(options.callback_typedefs);
+        assert_eq!(options.type_style, TypeStyle::Closure);
+        assert_eq!(options.type_references, TypeReferences::Inline);
+        assert_eq!(options.source_path, Some(PathBuf::from("src/user.ts")));
+        assert!(options.follow_imports);
+        assert_eq!(cli.output, Output::Edits);
+        assert!(cli.check);
+        assert!(cli.validate);
+        assert_eq!(cli.bind, Some(IpAddr::from([0, 0, 0, 0])));
+        assert_eq!(cli.jobs, Some(4));
+        assert_eq!(cli.cache_dir, Some(PathBuf::from("target/cache")));
+        assert_eq!(cli.min_coverage, Some(80.0));
+        assert_eq!(cli.min_exported_coverage, Some(99.5));
+        assert_eq!(cli.paths, [PathBuf::from("src"), PathBuf::from("lib/a.ts")]);
+        assert_eq!(cli.format, ReportFormat::Sarif);
+        assert_eq!(
+            cli.at,
+            Some(Cursor::LineColumn {
+                line: 3,
+                column: 14
+            })
+        );
+        assert_eq!(
+            options.verb_rules,
+            vec![("fetch".to_owned(), "Retrieves the {}.".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_from_args_errors() {
+        assert!(Cli::from_args(vec!["--min-lines".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--min-lines".to_owned(), "a".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--nope".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--at".to_owned(), "3".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--format".to_owned(), "xml".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--min-coverage".to_owned(), "101".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--min-coverage".to_owned(), "a".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--jobs".to_owned(), "0".to_owned()]).is_err());
+        assert!(Cli::from_args(vec!["--bind".to_owned(), "localhost".to_owned()]).is_err());
+        assert_eq!(
+            Cli::from_args(vec!["--cache".to_owned()])
+                .unwrap()
+                .cache_dir,
+            Some(PathBuf::from(".auto-js-doc-cache"))
+        );
+        assert!(Cli::from_args(vec!["--lines".to_owned(), "5-2".to_owned()]).is_err());
+        assert_eq!(
+            Cli::from_args(vec!["--offset".to_owned(), "12".to_owned()])
+                .unwrap()
+                .at,
+            Some(Cursor::Offset(12))
+        );
+        assert!(Cli::from_args(vec!["--verb-rule".to_owned(), "get".to_owned()]).is_err());
+    }
+}
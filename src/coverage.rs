@@ -0,0 +1,516 @@
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tree_sitter::{Node, Tree};
+
+use crate::options::{Options, ReportFormat};
+use crate::{
+    get_function_details_from_node, has_ignore_file_directive, walk, PendingComments, Visitor,
+};
+
+/// The kinds of declarations counted for coverage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Class,
+}
+
+/// A declaration counted for coverage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Declared with `export` at the top of the file
+    pub exported: bool,
+    /// Whether it has a JsDoc block
+    pub documented: bool,
+    /// One based
+    pub line: usize,
+}
+
+/// The declarations of a file, by path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileCoverage {
+    pub path: String,
+    pub symbols: Vec<Symbol>,
+}
+
+/// How many declarations have a JsDoc block
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Count {
+    pub documented: usize,
+    pub total: usize,
+}
+
+impl Count {
+    /// The percentage documented, 100 when there is nothing to document
+    pub fn percent(&self) -> f64 {
+        match self.total {
+            0 => 100.0,
+            total => self.documented as f64 * 100.0 / total as f64,
+        }
+    }
+
+    fn add(&mut self, symbol: &Symbol) {
+        self.total += 1;
+        self.documented += symbol.documented as usize;
+    }
+
+    // The ratio documented as Cobertura writes it
+    fn rate(&self) -> String {
+        format!("{:.4}", self.percent() / 100.0)
+    }
+
+    fn to_json(self) -> serde_json::Value {
+        json!({ "documented": self.documented, "total": self.total, "percent": self.percent() })
+    }
+}
+
+/// The counts for a file or for all of them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub functions: Count,
+    pub methods: Count,
+    pub classes: Count,
+    /// The exported functions and classes, which are counted in those too
+    pub exported: Count,
+    pub total: Count,
+}
+
+impl Summary {
+    /// Count the symbols of one or more files
+    pub fn new<'a>(symbols: impl IntoIterator<Item = &'a Symbol>) -> Summary {
+        let mut summary = Summary::default();
+        for symbol in symbols {
+            match symbol.kind {
+                SymbolKind::Function => summary.functions.add(symbol),
+                SymbolKind::Method => summary.methods.add(symbol),
+                SymbolKind::Class => summary.classes.add(symbol),
+            }
+            if symbol.exported {
+                summary.exported.add(symbol);
+            }
+            summary.total.add(symbol);
+        }
+        summary
+    }
+
+    fn to_json(self) -> serde_json::Value {
+        json!({
+            "functions": self.functions.to_json(),
+            "methods": self.methods.to_json(),
+            "classes": self.classes.to_json(),
+            "exported": self.exported.to_json(),
+            "total": self.total.to_json(),
+        })
+    }
+}
+
+/// Write the coverage of each file and of all of them in the format, `Eslint` and `Sarif` are
+/// diagnostic formats and write text
+pub fn format_coverage(format: ReportFormat, files: &[FileCoverage]) -> String {
+    match format {
+        ReportFormat::Json => to_json(files),
+        ReportFormat::Cobertura => cobertura(files, SystemTime::now()),
+        ReportFormat::Text | ReportFormat::Eslint | ReportFormat::Sarif => text(files),
+    }
+}
+
+// Find the declarations that get documented, along with the classes, whatever their blocks say
+pub(crate) fn coverage_tree(source_code: &str, tree: &Tree, options: &Options) -> Vec<Symbol> {
+    let root_node = tree.root_node();
+
+    let mut counting = Counting {
+        source_code,
+        options,
+        symbols: Vec::new(),
+    };
+    if !has_ignore_file_directive(source_code, &root_node) {
+        walk(&root_node, source_code, options, &mut counting);
+    }
+    counting.symbols
+}
+
+// Records the declarations as they are found
+struct Counting<'a> {
+    source_code: &'a str,
+    options: &'a Options,
+    symbols: Vec<Symbol>,
+}
+
+impl Visitor for Counting<'_> {
+    fn function(&mut self, node: &Node, pending: &PendingComments, exported: bool) {
+        let name = get_function_details_from_node(self.source_code, node).function_name;
+        let kind = match node.kind() {
+            "method_definition" => SymbolKind::Method,
+            _ => SymbolKind::Function,
+        };
+        let documented = pending.doc_block.is_some();
+        self.symbols
+            .push(symbol(name, kind, node, exported, documented));
+    }
+
+    fn class(&mut self, node: &Node, pending: &PendingComments, exported: bool) {
+        let name = node
+            .child_by_field_name("name")
+            .map_or("unknown", |name| {
+                name.utf8_text(self.source_code.as_bytes()).unwrap()
+            })
+            .to_owned();
+        let ignored = self
+            .options
+            .ignore_pattern
+            .as_ref()
+            .is_some_and(|pattern| pattern.is_match(&name));
+        if (!self.options.exported_only || exported) && !ignored {
+            let documented = pending.doc_block.is_some();
+            self.symbols
+                .push(symbol(name, SymbolKind::Class, node, exported, documented));
+        }
+    }
+}
+
+fn symbol(name: String, kind: SymbolKind, node: &Node, exported: bool, documented: bool) -> Symbol {
+    Symbol {
+        name,
+        kind,
+        exported,
+        documented,
+        line: node.start_position().row + 1,
+    }
+}
+
+// A table with a row per file and one for all of them
+fn text(files: &[FileCoverage]) -> String {
+    let header = [
+        "File",
+        "Functions",
+        "Methods",
+        "Classes",
+        "Exported",
+        "Total",
+    ];
+    let mut rows = vec![header.map(str::to_owned).to_vec()];
+    let mut row = |path: &str, summary: Summary| {
+        let counts = [
+            summary.functions,
+            summary.methods,
+            summary.classes,
+            summary.exported,
+            summary.total,
+        ];
+        let mut row = vec![path.to_owned()];
+        row.extend(counts.iter().map(|count| {
+            format!(
+                "{}/{} ({:.1}%)",
+                count.documented,
+                count.total,
+                count.percent()
+            )
+        }));
+        rows.push(row);
+    };
+    for file in files {
+        row(&file.path, Summary::new(&file.symbols));
+    }
+    row(
+        "All files",
+        Summary::new(files.iter().flat_map(|file| &file.symbols)),
+    );
+
+    let widths = (0..header.len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap())
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>();
+            cells.join("  ").trim_end().to_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn to_json(files: &[FileCoverage]) -> String {
+    let files_json = files
+        .iter()
+        .map(|file| {
+            json!({
+                "path": file.path,
+                "summary": Summary::new(&file.symbols).to_json(),
+                "symbols": file.symbols,
+            })
+        })
+        .collect::<Vec<_>>();
+    let summary = Summary::new(files.iter().flat_map(|file| &file.symbols));
+    serde_json::to_string_pretty(&json!({ "files": files_json, "summary": summary.to_json() }))
+        .unwrap()
+}
+
+// Cobertura's XML as read by CI dashboards, with a package per directory, a class per file and
+// a line per declaration that is hit when documented
+fn cobertura(files: &[FileCoverage], now: SystemTime) -> String {
+    let mut packages = BTreeMap::<&str, Vec<&FileCoverage>>::new();
+    for file in files {
+        let directory = file.path.rsplit_once('/').map_or(".", |(dir, _)| dir);
+        packages.entry(directory).or_default().push(file);
+    }
+
+    let total = Summary::new(files.iter().flat_map(|file| &file.symbols)).total;
+    let timestamp = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let mut xml = String::from("<?xml version=\"1.0\" ?>\n");
+    xml.push_str(
+        "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">\n",
+    );
+    xml.push_str(&format!(
+        "<coverage line-rate=\"{}\" branch-rate=\"0\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"0\" branches-valid=\"0\" complexity=\"0\" version=\"{}\" timestamp=\"{}\">\n",
+        total.rate(),
+        total.documented,
+        total.total,
+        env!("CARGO_PKG_VERSION"),
+        timestamp
+    ));
+    xml.push_str("  <sources>\n    <source>.</source>\n  </sources>\n  <packages>\n");
+    for (directory, files) in packages {
+        let count = Summary::new(files.iter().flat_map(|file| &file.symbols)).total;
+        xml.push_str(&format!(
+            "    <package name=\"{}\" line-rate=\"{}\" branch-rate=\"0\" complexity=\"0\">\n      <classes>\n",
+            escape(directory),
+            count.rate()
+        ));
+        for file in files {
+            let count = Summary::new(&file.symbols).total;
+            xml.push_str(&format!(
+                "        <class name=\"{}\" filename=\"{}\" line-rate=\"{}\" branch-rate=\"0\" complexity=\"0\">\n          <methods/>\n          <lines>\n",
+                escape(file.path.rsplit('/').next().unwrap()),
+                escape(&file.path),
+                count.rate()
+            ));
+            for symbol in &file.symbols {
+                xml.push_str(&format!(
+                    "            <line number=\"{}\" hits=\"{}\"/>\n",
+                    symbol.line, symbol.documented as u8
+                ));
+            }
+            xml.push_str("          </lines>\n        </class>\n");
+        }
+        xml.push_str("      </classes>\n    </package>\n");
+    }
+    xml.push_str("  </packages>\n</coverage>");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coverage(source_code: &str, options: &Options) -> Vec<Symbol> {
+        coverage_tree(source_code, &crate::parse(source_code), options)
+    }
+
+    #[test]
+    fn test_coverage() {
+        let source_code = r#"
+/** Adds */
+export function add(a: number, b: number) {}
+
+function helper() {}
+
+// auto-js-doc-ignore
+function ignored() {}
+
+/**
+ * A user
+ */
+export class User {
+  /** Makes a user */
+  constructor(name: string) {}
+
+  // Not a JsDoc block
+  greet() {}
+
+  private secret() {}
+}
+
+class Internal {}
+"#;
+        let symbols = coverage(source_code, &Options::default());
+        let names = symbols
+            .iter()
+            .map(|symbol| (symbol.name.as_str(), symbol.kind, symbol.documented))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("add", SymbolKind::Function, true),
+                ("helper", SymbolKind::Function, false),
+                ("User", SymbolKind::Class, true),
+                ("constructor", SymbolKind::Method, true),
+                ("greet", SymbolKind::Method, false),
+                ("secret", SymbolKind::Method, false),
+                ("Internal", SymbolKind::Class, false),
+            ]
+        );
+        assert_eq!(symbols[0].line, 3);
+
+        let summary = Summary::new(&symbols);
+        assert_eq!(
+            summary.functions,
+            Count {
+                documented: 1,
+                total: 2
+            }
+        );
+        assert_eq!(
+            summary.methods,
+            Count {
+                documented: 1,
+                total: 3
+            }
+        );
+        assert_eq!(
+            summary.classes,
+            Count {
+                documented: 1,
+                total: 2
+            }
+        );
+        assert_eq!(
+            summary.exported,
+            Count {
+                documented: 2,
+                total: 2
+            }
+        );
+        assert_eq!(summary.total.percent(), 3.0 * 100.0 / 7.0);
+
+        // The same filters as documenting
+        let options = Options {
+            exported_only: true,
+            public_only: true,
+            ..Options::default()
+        };
+        let names = coverage(source_code, &options)
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["add", "User", "constructor", "greet"]);
+
+        let source_code = "// auto-js-doc-ignore-file\nfunction a() {}";
+        assert!(coverage(source_code, &Options::default()).is_empty());
+        assert_eq!(Summary::new(&[]).total.percent(), 100.0);
+    }
+
+    #[test]
+    fn test_coverage_matches_documenting() {
+        let source_code = r#"
+function short() {}
+
+/* auto-js-doc-disable */
+function disabled() {}
+/* auto-js-doc-enable */
+
+export class A {
+  b() {
+    return 1;
+  }
+
+  c() {}
+}
+
+function long() {
+  return 1;
+}
+"#;
+        let options = Options {
+            min_lines: Some(2),
+            ..Options::default()
+        };
+        let methods_and_functions = coverage(source_code, &options)
+            .into_iter()
+            .filter(|symbol| symbol.kind != SymbolKind::Class)
+            .map(|symbol| symbol.name)
+            .collect::<Vec<_>>();
+        let documented = crate::document(source_code, &options)
+            .functions
+            .into_iter()
+            .map(|function| function.name)
+            .collect::<Vec<_>>();
+        assert_eq!(methods_and_functions, ["b", "long"]);
+        assert_eq!(methods_and_functions, documented);
+    }
+
+    fn files() -> Vec<FileCoverage> {
+        let source_code = "/** A */\nfunction a() {}\nexport function b() {}\n";
+        vec![
+            FileCoverage {
+                path: "src/a.ts".to_owned(),
+                symbols: coverage(source_code, &Options::default()),
+            },
+            FileCoverage {
+                path: "src/lib/<b>.ts".to_owned(),
+                symbols: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            format_coverage(ReportFormat::Text, &files()),
+            "\
+File            Functions     Methods       Classes       Exported      Total
+src/a.ts        1/2 (50.0%)   0/0 (100.0%)  0/0 (100.0%)  0/1 (0.0%)    1/2 (50.0%)
+src/lib/<b>.ts  0/0 (100.0%)  0/0 (100.0%)  0/0 (100.0%)  0/0 (100.0%)  0/0 (100.0%)
+All files       1/2 (50.0%)   0/0 (100.0%)  0/0 (100.0%)  0/1 (0.0%)    1/2 (50.0%)"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&format_coverage(ReportFormat::Json, &files())).unwrap();
+        assert_eq!(json["files"][0]["path"], "src/a.ts");
+        assert_eq!(json["files"][0]["summary"]["functions"]["percent"], 50.0);
+        assert_eq!(json["files"][0]["symbols"][1]["name"], "b");
+        assert_eq!(json["files"][0]["symbols"][1]["kind"], "function");
+        assert_eq!(json["files"][0]["symbols"][1]["exported"], true);
+        assert_eq!(json["files"][0]["symbols"][1]["documented"], false);
+        assert_eq!(json["files"][0]["symbols"][1]["line"], 3);
+        assert_eq!(json["summary"]["total"]["documented"], 1);
+        assert_eq!(json["summary"]["exported"]["total"], 1);
+    }
+
+    #[test]
+    fn test_cobertura() {
+        let xml = cobertura(&files(), UNIX_EPOCH);
+        assert!(xml.contains(
+            "<coverage line-rate=\"0.5000\" branch-rate=\"0\" lines-covered=\"1\" lines-valid=\"2\""
+        ));
+        assert!(xml.contains("timestamp=\"0\">"));
+        assert!(xml.contains("<package name=\"src\" line-rate=\"0.5000\""));
+        assert!(xml.contains("<package name=\"src/lib\" line-rate=\"1.0000\""));
+        assert!(xml.contains("<class name=\"a.ts\" filename=\"src/a.ts\" line-rate=\"0.5000\""));
+        assert!(xml.contains("filename=\"src/lib/&lt;b&gt;.ts\""));
+        assert!(xml.contains(
+            "<line number=\"2\" hits=\"1\"/>\n            <line number=\"3\" hits=\"0\"/>"
+        ));
+        assert!(xml.ends_with("</coverage>"));
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::documenter::Documentation;
+use crate::edits::Position;
+
+/// The rule for functions without a JsDoc block
+pub const MISSING_JSDOC: &str = "auto-js-doc/missing-jsdoc";
+/// The rule for JsDoc blocks that differ from the one that would be generated
+pub const OUTDATED_JSDOC: &str = "auto-js-doc/outdated-jsdoc";
+/// The rule for `@param` tags naming something that isn't a parameter
+pub const UNKNOWN_PARAM: &str = "auto-js-doc/unknown-param";
+/// The rule for parameters without a `@param` tag
+pub const MISSING_PARAM: &str = "auto-js-doc/missing-param";
+/// The rule for `@param` tags in a different order to the parameters
+pub const PARAM_ORDER: &str = "auto-js-doc/param-order";
+/// The rule for `@param` and `@returns` types that differ from the annotations
+pub const TYPE_MISMATCH: &str = "auto-js-doc/type-mismatch";
+/// The rule for `@returns` tags on functions that return nothing
+pub const VOID_RETURNS: &str = "auto-js-doc/void-returns";
+/// The rule for `@param` tags that disagree with the parameter on being optional or its default
+pub const OPTIONAL_MISMATCH: &str = "auto-js-doc/optional-mismatch";
+
+/// A problem with the documentation of a function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub message: String,
+    /// The function name
+    pub name: String,
+    pub range: Range<usize>,
+    pub start: Position,
+    pub end: Position,
+    /// How to fix it, for the problems found validating an existing block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(source_code: &str, rule: &'static str, name: &str, range: Range<usize>) -> Diagnostic {
+        let message = match rule {
+            MISSING_JSDOC => format!("Missing JSDoc for `{}`", name),
+            _ => format!("Outdated JSDoc for `{}`", name),
+        };
+        Diagnostic {
+            rule,
+            message,
+            name: name.to_owned(),
+            start: Position::of(source_code, range.start),
+            end: Position::of(source_code, range.end),
+            range,
+            suggestion: None,
+        }
+    }
+
+    pub(crate) fn with_suggestion(
+        source_code: &str,
+        rule: &'static str,
+        name: &str,
+        range: Range<usize>,
+        message: String,
+        suggestion: String,
+    ) -> Diagnostic {
+        Diagnostic {
+            message,
+            suggestion: Some(suggestion),
+            ..Diagnostic::new(source_code, rule, name, range)
+        }
+    }
+
+    /// The message followed by the suggestion, if there is one
+    pub fn message_with_suggestion(&self) -> String {
+        match &self.suggestion {
+            Some(suggestion) => format!("{}. {}", self.message, suggestion),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// Report the functions that are missing a JsDoc block, on their first line, or have an outdated one
+pub fn diagnostics(source_code: &str, documentation: &Documentation) -> Vec<Diagnostic> {
+    documentation
+        .functions
+        .iter()
+        .filter_map(|function| match &function.existing_doc {
+            None => {
+                let start = function.range.start;
+                let end = source_code[start..function.range.end]
+                    .find('\n')
+                    .map_or(function.range.end, |n| start + n);
+                Some(Diagnostic::new(
+                    source_code,
+                    MISSING_JSDOC,
+                    &function.name,
+                    start..end,
+                ))
+            }
+            Some(doc) if function.is_outdated(source_code) => Some(Diagnostic::new(
+                source_code,
+                OUTDATED_JSDOC,
+                &function.name,
+                doc.clone(),
+            )),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Documenter, Options};
+
+    #[test]
+    fn test_diagnostics() {
+        let source_code = "/**\n * a\n */\nfunction a() {}\n/**\n * b\n */\nfunction b(c: string) {}\nclass D {\n  e() {\n  }\n}\n";
+        let documentation = Documenter::new()
+            .options(Options::default())
+            .document(source_code);
+        let diagnostics = diagnostics(source_code, &documentation);
+
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|d| (d.rule, d.message.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (OUTDATED_JSDOC, "Outdated JSDoc for `b`"),
+                (MISSING_JSDOC, "Missing JSDoc for `e`"),
+            ]
+        );
+        assert_eq!(diagnostics[0].start, Position { line: 4, column: 0 });
+        assert_eq!(diagnostics[0].end, Position { line: 6, column: 3 });
+        assert_eq!(diagnostics[1].start, Position { line: 9, column: 2 });
+        assert_eq!(diagnostics[1].end, Position { line: 9, column: 7 });
+    }
+
+    #[test]
+    fn test_described_blocks_are_not_outdated() {
+        let source_code = "/**\n * Finds the user.\n * Looks in the cache first.\n *\n * @param {string} id - The user's id\n * @param {number} [retries] - How many times to try\n * @returns {User} The user\n * @example\n * find(\"a\")\n */\nfunction find(id: string, retries?: number): User {}\n/**\n * @param {number} id - The user's id\n * @param {number} [retries]\n */\nfunction load(id: string, retries?: number) {}\n";
+        let documentation = Documenter::new().document(source_code);
+
+        assert_eq!(
+            diagnostics(source_code, &documentation)
+                .iter()
+                .map(|d| (d.rule, d.name.as_str()))
+                .collect::<Vec<_>>(),
+            [(OUTDATED_JSDOC, "load")]
+        );
+        assert!(documentation.functions[0].edits.is_empty());
+    }
+}
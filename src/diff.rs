@@ -0,0 +1,121 @@
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// The changed line ranges of each file in a unified diff, as written by `git diff --unified=0`
+///
+/// Lines are one based and refer to the new version of the file, deleted files are left out.
+pub fn parse_unified_diff(diff: &str) -> Vec<(PathBuf, Vec<RangeInclusive<usize>>)> {
+    let mut files: Vec<(PathBuf, Vec<RangeInclusive<usize>>)> = Vec::new();
+    let mut in_file = false;
+    // Lines left in the current hunk on the old and new side, so `+++` in them isn't read as a header
+    let (mut old_left, mut new_left) = (0usize, 0usize);
+
+    for line in diff.lines() {
+        if old_left > 0 || new_left > 0 {
+            match line.chars().next() {
+                Some('-') => old_left = old_left.saturating_sub(1),
+                Some('+') => new_left = new_left.saturating_sub(1),
+                Some('\\') => {}
+                _ => {
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+            continue;
+        }
+
+        if let Some(path) = line.strip_prefix("+++ ") {
+            in_file = path != "/dev/null";
+            if in_file {
+                let path = path.strip_prefix("b/").unwrap_or(path);
+                // Some diffs add a timestamp after a tab
+                let path = path.split('\t').next().unwrap_or(path);
+                files.push((PathBuf::from(path), Vec::new()));
+            }
+        } else if line.starts_with("diff ") {
+            in_file = false;
+        } else if let Some(hunk) = line.strip_prefix("@@ ").and_then(parse_hunk) {
+            (old_left, new_left) = (hunk.old_count, hunk.new_count);
+            if let (true, Some((_, ranges))) = (in_file, files.last_mut()) {
+                ranges.push(hunk.changed_lines());
+            }
+        }
+    }
+
+    files
+}
+
+// A hunk header, `@@ -3,2 +4,5 @@`
+#[derive(Debug, PartialEq, Eq)]
+struct Hunk {
+    old_count: usize,
+    new_start: usize,
+    new_count: usize,
+}
+
+impl Hunk {
+    // The lines changed in the new version of the file
+    fn changed_lines(&self) -> RangeInclusive<usize> {
+        if self.new_count == 0 {
+            // Only lines removed after `new_start`, so count the lines on either side as changed
+            self.new_start.max(1)..=self.new_start + 1
+        } else {
+            self.new_start..=self.new_start + self.new_count - 1
+        }
+    }
+}
+
+fn parse_hunk(header: &str) -> Option<Hunk> {
+    let mut parts = header.split_whitespace();
+    let (_, old_count) = parse_side(parts.next()?.strip_prefix('-')?)?;
+    let (new_start, new_count) = parse_side(parts.next()?.strip_prefix('+')?)?;
+    Some(Hunk {
+        old_count,
+        new_start,
+        new_count,
+    })
+}
+
+// `4,5` -> `(4, 5)`, the count is one when left out
+fn parse_side(side: &str) -> Option<(usize, usize)> {
+    match side.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((side.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unified_diff() {
+        let diff = "\
+diff --git a/src/a.ts b/src/a.ts
+index 1..2 100644
+--- a/src/a.ts
++++ b/src/a.ts
+@@ -3 +3 @@ function a() {
+-old
++new
+@@ -10,0 +11,3 @@
++one
++++ two
++three
+@@ -20,2 +22,0 @@
+-gone
+-gone
+diff --git a/b.ts b/b.ts
+deleted file mode 100644
+--- a/b.ts
++++ /dev/null
+@@ -1,2 +0,0 @@
+-a
+-b
+";
+        assert_eq!(
+            parse_unified_diff(diff),
+            vec![(PathBuf::from("src/a.ts"), vec![3..=3, 11..=13, 22..=23])]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+/// Inline comments that control what gets documented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+    /// `// auto-js-doc-ignore` skips the next declaration
+    Ignore,
+    /// `// auto-js-doc-ignore-file` at the top of a file leaves the whole file untouched
+    IgnoreFile,
+    /// `/* auto-js-doc-disable */` skips every declaration until re-enabled
+    Disable,
+    /// `/* auto-js-doc-enable */` ends a disabled region
+    Enable,
+}
+
+impl Directive {
+    /// Parse a raw comment, including its `//` or `/* */` markers
+    pub fn parse(comment: &str) -> Option<Directive> {
+        let comment = comment.trim();
+        let text = if let Some(line) = comment.strip_prefix("//") {
+            line
+        } else if let Some(block) = comment.strip_prefix("/*") {
+            block.strip_suffix("*/").unwrap_or(block)
+        } else {
+            comment
+        };
+
+        match text.trim() {
+            "auto-js-doc-ignore" => Some(Directive::Ignore),
+            "auto-js-doc-ignore-file" => Some(Directive::IgnoreFile),
+            "auto-js-doc-disable" => Some(Directive::Disable),
+            "auto-js-doc-enable" => Some(Directive::Enable),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Directive::parse("// auto-js-doc-ignore"),
+            Some(Directive::Ignore)
+        );
+        assert_eq!(
+            Directive::parse("//auto-js-doc-ignore-file"),
+            Some(Directive::IgnoreFile)
+        );
+        assert_eq!(
+            Directive::parse("/* auto-js-doc-disable */"),
+            Some(Directive::Disable)
+        );
+        assert_eq!(
+            Directive::parse("/*auto-js-doc-enable*/"),
+            Some(Directive::Enable)
+        );
+        assert_eq!(Directive::parse("// auto-js-doc-ignore this"), None);
+        assert_eq!(Directive::parse("// my comment"), None);
+    }
+}
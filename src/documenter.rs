@@ -0,0 +1,109 @@
+use crate::coverage::{coverage_tree, Symbol};
+use crate::diagnostics::{diagnostics, Diagnostic};
+use crate::edits::{apply_edits, Edit};
+use crate::options::Options;
+use crate::structs::FunctionDoc;
+use crate::validate::validate_tree;
+use std::ops::RangeInclusive;
+
+/// The changes to make to a source file and what was documented
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Documentation {
+    /// The edits adding the JsDoc blocks, apply them with [`apply_edits`]
+    pub edits: Vec<Edit>,
+    /// The functions that get a JsDoc block, in source order
+    pub functions: Vec<FunctionDoc>,
+}
+
+impl Documentation {
+    /// The functions missing a JsDoc block or with an outdated one in the source this documents
+    pub fn diagnostics(&self, source_code: &str) -> Vec<Diagnostic> {
+        diagnostics(source_code, self)
+    }
+}
+
+/// Adds JsDoc blocks to TypeScript sources
+///
+/// ```
+/// use auto_js_doc::{Documenter, Options};
+///
+/// let mut options = Options::default();
+/// options.exported_only = true;
+/// let documenter = Documenter::new().options(options);
+/// let edits = documenter.edits("export function a(b: string) {}");
+/// assert_eq!(edits.len(), 1);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Documenter {
+    options: Options,
+}
+
+impl Documenter {
+    pub fn new() -> Documenter {
+        Documenter::default()
+    }
+
+    /// Set the options used for every source
+    pub fn options(mut self, options: Options) -> Documenter {
+        self.options = options;
+        self
+    }
+
+    /// Work out the edits and signature data for a source
+    pub fn document(&self, source_code: &str) -> Documentation {
+        crate::document(source_code, &self.options)
+    }
+
+    /// Document only the declarations touching the one based line ranges, like the changed lines of a diff
+    pub fn document_lines(
+        &self,
+        source_code: &str,
+        line_ranges: &[RangeInclusive<usize>],
+    ) -> Documentation {
+        let options = Options {
+            line_ranges: line_ranges.to_vec(),
+            ..self.options.clone()
+        };
+        crate::document(source_code, &options)
+    }
+
+    /// Document only the innermost function or method containing the byte offset, ignoring the filters
+    ///
+    /// The returned edits insert the block in front of it and leave the rest of the source untouched.
+    pub fn document_at(&self, source_code: &str, offset: usize) -> Option<FunctionDoc> {
+        crate::document_at(source_code, &self.options, offset)
+    }
+
+    /// The edits that add the JsDoc blocks, as byte ranges of the source and their replacements
+    pub fn edits(&self, source_code: &str) -> Vec<Edit> {
+        self.document(source_code).edits
+    }
+
+    /// The signature data of the functions that get a JsDoc block
+    pub fn functions(&self, source_code: &str) -> Vec<FunctionDoc> {
+        self.document(source_code).functions
+    }
+
+    /// The functions missing a JsDoc block or with an outdated one
+    pub fn check(&self, source_code: &str) -> Vec<Diagnostic> {
+        diagnostics(source_code, &self.document(source_code))
+    }
+
+    /// The problems with the existing JsDoc blocks, like `@param` tags for parameters that aren't
+    /// there, each with a suggestion to fix it
+    pub fn validate(&self, source_code: &str) -> Vec<Diagnostic> {
+        let tree = crate::parse(source_code);
+        let documentation = crate::document_tree(source_code, &tree, &self.options);
+        validate_tree(source_code, &tree, &documentation)
+    }
+
+    /// The functions, methods and classes that would be documented, and whether they are
+    pub fn coverage(&self, source_code: &str) -> Vec<Symbol> {
+        coverage_tree(source_code, &crate::parse(source_code), &self.options)
+    }
+
+    /// The source with the JsDoc blocks added
+    pub fn process(&self, source_code: &str) -> String {
+        apply_edits(source_code, &self.edits(source_code))
+    }
+}
@@ -1,6 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use crate::process;
+    use crate::{
+        apply_edits, process, process_with_options, DescriptionStyle, Documenter, Edit,
+        FunctionDoc, Options, ParamDoc, Position, TypeReferences, TypeStyle,
+    };
+    use regex::Regex;
 
     #[test]
     fn test_basic() {
@@ -251,7 +255,7 @@ mod tests {
 
         let expected_output = r#"
             class A {
-
+                
                 /**
                  * my class does something fun.
                  *
@@ -346,5 +350,1270 @@ mod tests {
         assert_eq!(updated_code, expected_output);
     }
 
-    // TODO there are some issues with honoring the whitespace between comments within and out of classes
+    #[test]
+    fn test_outdated_doc_block_without_description() {
+        let source_code = r#"
+        /** @param {string} old */
+        function a(new: string) {}
+        "#;
+
+        let expected_output = r#"
+        /**
+         * a
+         *
+         * @param {string} new - 
+         */
+        function a(new: string) {}
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_exported_only() {
+        let source_code = r#"
+            // internal helper
+            function helper(a: string) {
+
+            }
+
+            export function api(a: string) {
+
+            }
+
+            class Internal {
+                run() {}
+            }
+
+            export class Public {
+                run() {}
+            }
+        "#;
+
+        let expected_output = r#"
+            // internal helper
+            function helper(a: string) {
+
+            }
+
+            /**
+             * api
+             *
+             * @param {string} a - 
+             */
+            export function api(a: string) {
+
+            }
+
+            class Internal {
+                run() {}
+            }
+
+            export class Public {
+                /**
+                 * run
+                 */
+                run() {}
+            }
+        "#;
+
+        let options = Options {
+            exported_only: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_member_filters() {
+        let source_code = r#"
+            class A {
+                constructor(a: string) {}
+
+                public a() {}
+
+                protected b() {}
+
+                private c() {}
+
+                #d() {}
+
+                _e() {}
+
+                f() {
+                    // TODO
+                }
+            }
+        "#;
+
+        let expected_output = r#"
+            class A {
+                constructor(a: string) {}
+
+                public a() {}
+
+                protected b() {}
+
+                private c() {}
+
+                #d() {}
+
+                _e() {}
+
+                /**
+                 * f
+                 */
+                f() {
+                    // TODO
+                }
+            }
+        "#;
+
+        let options = Options {
+            public_only: true,
+            skip_constructors: true,
+            min_lines: Some(2),
+            ignore_pattern: Some(Regex::new("^_").unwrap()),
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_ignore_directive() {
+        let source_code = r#"
+            // auto-js-doc-ignore
+            function a(param: string) {
+
+            }
+
+            class B {
+                // auto-js-doc-ignore
+                c() {}
+
+                // does d
+                d() {}
+            }
+        "#;
+
+        let expected_output = r#"
+            // auto-js-doc-ignore
+            function a(param: string) {
+
+            }
+
+            class B {
+                // auto-js-doc-ignore
+                c() {}
+
+                /**
+                 * does d
+                 */
+                d() {}
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_ignore_file_directive() {
+        let source_code = r#"
+            // auto-js-doc-ignore-file
+            function a(param: string) {
+
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, source_code);
+    }
+
+    #[test]
+    fn test_disable_enable_directives() {
+        let source_code = r#"
+            /* auto-js-doc-disable */
+            function a() {
+
+            }
+
+            class B {
+                c() {}
+            }
+            /* auto-js-doc-enable */
+            function d() {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            /* auto-js-doc-disable */
+            function a() {
+
+            }
+
+            class B {
+                c() {}
+            }
+            /* auto-js-doc-enable */
+            /**
+             * d
+             */
+            function d() {
+
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_humanized_descriptions() {
+        let source_code = r#"
+            export function getUserById(id: string) {
+
+            }
+
+            class UserService {
+                constructor() {}
+
+                isValid() {}
+
+                // already described
+                fetchAll() {}
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * Gets the user by id.
+             *
+             * @param {string} id - 
+             */
+            export function getUserById(id: string) {
+
+            }
+
+            class UserService {
+                /**
+                 * Creates an instance of UserService.
+                 */
+                constructor() {}
+
+                /**
+                 * Checks whether valid.
+                 */
+                isValid() {}
+
+                /**
+                 * already described
+                 */
+                fetchAll() {}
+            }
+        "#;
+
+        let options = Options {
+            description: DescriptionStyle::Humanize,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_todo_descriptions() {
+        let source_code = r#"
+            function a() {}
+        "#;
+
+        let expected_output = r#"
+            /**
+             * TODO
+             */
+            function a() {}
+        "#;
+
+        let options = Options {
+            description: DescriptionStyle::Todo,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_param_comments() {
+        let source_code = r#"
+            interface RetryOptions {
+                /** max retries */
+                retries: number;
+                backoff?: boolean; // double the delay each time
+            }
+
+            function request(
+                timeout: number, // in ms
+                /** where to send it */ url: string,
+                options: RetryOptions,
+            ) {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            interface RetryOptions {
+                /** max retries */
+                retries: number;
+                backoff?: boolean; // double the delay each time
+            }
+
+            /**
+             * request
+             *
+             * @param {number} timeout - in ms
+             * @param {string} url - where to send it
+             * @param {RetryOptions} options - 
+             * @param {number} options.retries - max retries
+             * @param {boolean} [options.backoff] - double the delay each time
+             */
+            function request(
+                timeout: number, // in ms
+                /** where to send it */ url: string,
+                options: RetryOptions,
+            ) {
+
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_strip_param_comments() {
+        let source_code = r#"
+            function request(
+                timeout: number, // in ms
+                // where to send it
+                url: string,
+                /** headers to add */ headers: { accept: string; /* mime type */ },
+            ) {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * request
+             *
+             * @param {number} timeout - in ms
+             * @param {string} url - where to send it
+             * @param {{ accept: string; }} headers - headers to add
+             * @param {string} headers.accept - mime type
+             */
+            function request(
+                timeout: number,
+                url: string,
+                headers: { accept: string; },
+            ) {
+
+            }
+        "#;
+
+        let options = Options {
+            strip_param_comments: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_strip_param_comments_into_existing_block() {
+        let source_code = r#"
+            /**
+             * Sends a request.
+             *
+             * @param {number} timeout - 
+             * @example
+             *   request(10);
+             */
+            function request(
+                timeout: number, // in ms
+            ) {}
+        "#;
+
+        let expected_output = r#"
+            /**
+             * Sends a request.
+             *
+             * @param {number} timeout - in ms
+             * @example
+             *   request(10);
+             */
+            function request(
+                timeout: number,
+            ) {}
+        "#;
+
+        let options = Options {
+            strip_param_comments: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_infer_returns() {
+        let source_code = r#"
+            export async function load(id: string) {
+                return new User(id);
+            }
+
+            function log(message: string) {
+                console.log(message);
+            }
+
+            function* ids() {
+                yield 1;
+            }
+
+            class A {
+                constructor() {}
+
+                same(value: string) {
+                    return value;
+                }
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * load
+             *
+             * @param {string} id - 
+             * @returns {Promise<User>} 
+             */
+            export async function load(id: string) {
+                return new User(id);
+            }
+
+            /**
+             * log
+             *
+             * @param {string} message - 
+             * @returns {void} 
+             */
+            function log(message: string) {
+                console.log(message);
+            }
+
+            /**
+             * ids
+             *
+             * @generator
+             * @yields {number} 
+             */
+            function* ids() {
+                yield 1;
+            }
+
+            class A {
+                /**
+                 * constructor
+                 */
+                constructor() {}
+
+                /**
+                 * same
+                 *
+                 * @param {string} value - 
+                 * @returns {string} 
+                 */
+                same(value: string) {
+                    return value;
+                }
+            }
+        "#;
+
+        let options = Options {
+            infer_returns: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_skip_void_returns() {
+        let source_code = r#"
+            function log(message: string) {
+                console.log(message);
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * log
+             *
+             * @param {string} message - 
+             */
+            function log(message: string) {
+                console.log(message);
+            }
+        "#;
+
+        let options = Options {
+            infer_returns: true,
+            skip_void_returns: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_infer_throws() {
+        let source_code = r#"
+            function parse(input: string): number {
+                if (!input) {
+                    throw new ValidationError("empty");
+                }
+                return Number(input);
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * parse
+             *
+             * @param {string} input - 
+             * @returns {number} 
+             * @throws {ValidationError} 
+             */
+            function parse(input: string): number {
+                if (!input) {
+                    throw new ValidationError("empty");
+                }
+                return Number(input);
+            }
+        "#;
+
+        let options = Options {
+            infer_throws: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_infer_throws_with_import_references() {
+        let source_code = r#"
+            import { ValidationError } from "./errors";
+            function parse(input: string): number {
+                throw new ValidationError("empty");
+            }
+        "#;
+
+        let expected_output = r#"
+            import { ValidationError } from "./errors";
+            /**
+             * parse
+             *
+             * @param {string} input - 
+             * @returns {number} 
+             * @throws {import("./errors").ValidationError} 
+             */
+            function parse(input: string): number {
+                throw new ValidationError("empty");
+            }
+        "#;
+
+        let options = Options {
+            infer_throws: true,
+            type_references: TypeReferences::Import,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_generators() {
+        let source_code = r#"
+            export function* range(end: number): Generator<number, string, unknown> {
+                return "done";
+            }
+
+            async function* pages(): AsyncGenerator<Page, void> {
+
+            }
+
+            class A {
+                *items() {
+                    yield "a";
+                }
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * range
+             *
+             * @param {number} end - 
+             * @generator
+             * @yields {number} 
+             * @returns {string} 
+             */
+            export function* range(end: number): Generator<number, string, unknown> {
+                return "done";
+            }
+
+            /**
+             * pages
+             *
+             * @generator
+             * @yields {Page} 
+             */
+            async function* pages(): AsyncGenerator<Page, void> {
+
+            }
+
+            class A {
+                /**
+                 * items
+                 *
+                 * @generator
+                 * @yields {string} 
+                 */
+                *items() {
+                    yield "a";
+                }
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_callback_typedefs() {
+        let source_code = r#"
+            class Loader {
+                load(url: string, onDone: (err: Error | null, value?: string) => void) {
+
+                }
+            }
+        "#;
+
+        let expected_output = r#"
+            class Loader {
+                /**
+                 * @callback LoadOnDoneCallback
+                 * @param {Error | null} err - 
+                 * @param {string} [value] - 
+                 * @returns {void} 
+                 */
+                /**
+                 * load
+                 *
+                 * @param {string} url - 
+                 * @param {LoadOnDoneCallback} onDone - 
+                 */
+                load(url: string, onDone: (err: Error | null, value?: string) => void) {
+
+                }
+            }
+        "#;
+
+        let options = Options {
+            callback_typedefs: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+
+        // Running again regenerates the callback rather than adding another
+        let updated_code = process_with_options(&updated_code, &options);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_callback_typedefs_with_the_same_param_name() {
+        let source_code = r#"
+            function fetchUser(onDone: (user: string) => void) {}
+            function fetchPost(onDone: (post: number) => void) {}
+        "#;
+
+        let expected_output = r#"
+            /**
+             * @callback FetchUserOnDoneCallback
+             * @param {string} user - 
+             * @returns {void} 
+             */
+            /**
+             * fetchUser
+             *
+             * @param {FetchUserOnDoneCallback} onDone - 
+             */
+            function fetchUser(onDone: (user: string) => void) {}
+            /**
+             * @callback FetchPostOnDoneCallback
+             * @param {number} post - 
+             * @returns {void} 
+             */
+            /**
+             * fetchPost
+             *
+             * @param {FetchPostOnDoneCallback} onDone - 
+             */
+            function fetchPost(onDone: (post: number) => void) {}
+        "#;
+
+        let options = Options {
+            callback_typedefs: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_multiline_types_are_normalized() {
+        let source_code = r#"
+            function a(
+                value: Map<
+                    string, // the key
+                    number
+                >,
+            ): Promise<
+                void
+            > {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * a
+             *
+             * @param {Map<string, number>} value - 
+             * @returns {Promise<void>} 
+             */
+            function a(
+                value: Map<
+                    string, // the key
+                    number
+                >,
+            ): Promise<
+                void
+            > {
+
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_closure_types() {
+        let source_code = r#"
+            function a(names: readonly string[], id: string | null, data: any, key: keyof User): number[] {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            /**
+             * a
+             *
+             * @param {Array<string>} names - 
+             * @param {?string} id - 
+             * @param {*} data - 
+             * @param {*} key - 
+             * @returns {Array<number>} 
+             */
+            function a(names: readonly string[], id: string | null, data: any, key: keyof User): number[] {
+
+            }
+        "#;
+
+        let options = Options {
+            type_style: TypeStyle::Closure,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_inline_type_aliases() {
+        let source_code = r#"
+            type Id = string;
+            type Status = "on" | "off";
+            function a(id: Id, statuses: Status[]): Id {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            type Id = string;
+            type Status = "on" | "off";
+            /**
+             * a
+             *
+             * @param {string} id - 
+             * @param {("on" | "off")[]} statuses - 
+             * @returns {string} 
+             */
+            function a(id: Id, statuses: Status[]): Id {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::Inline,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_see_type_references() {
+        let source_code = r#"
+            import { User } from "./user";
+            interface Options {}
+            function a(user: User, options: Options): string {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            import { User } from "./user";
+            interface Options {}
+            /**
+             * a
+             *
+             * @param {User} user - 
+             * @param {Options} options - 
+             * @returns {string} 
+             * @see {@link User} from "./user"
+             * @see {@link Options}
+             */
+            function a(user: User, options: Options): string {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::See,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_import_type_references() {
+        let source_code = r#"
+            import { User as Account } from "./user";
+            import * as models from "../models";
+            function a(account: Account, post: models.Post): void {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            import { User as Account } from "./user";
+            import * as models from "../models";
+            /**
+             * a
+             *
+             * @param {import("./user").User} account - 
+             * @param {import("../models").Post} post - 
+             * @returns {void} 
+             */
+            function a(account: Account, post: models.Post): void {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::Import,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_follow_imports() {
+        let dir =
+            std::env::temp_dir().join(format!("auto-js-doc-follow-imports-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ids.ts"), "export type UserId = number;\n").unwrap();
+
+        let source_code = r#"
+            import { UserId } from "./ids";
+            function a(id: UserId) {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            import { UserId } from "./ids";
+            /**
+             * a
+             *
+             * @param {number} id - 
+             */
+            function a(id: UserId) {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::Inline,
+            source_path: Some(dir.join("main.ts")),
+            follow_imports: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        std::fs::remove_dir_all(&dir).unwrap();
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_follow_imports_resolves_in_the_imported_file() {
+        let dir = std::env::temp_dir().join(format!(
+            "auto-js-doc-follow-imports-{}-own-table",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("user.ts"),
+            "type Id = number;\nexport type User = { id: Id };\n",
+        )
+        .unwrap();
+
+        let source_code = r#"
+            import { User } from "./user.js";
+            type Id = string;
+            function a(user: User) {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            import { User } from "./user.js";
+            type Id = string;
+            /**
+             * a
+             *
+             * @param {{ id: number }} user - 
+             */
+            function a(user: User) {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::Inline,
+            source_path: Some(dir.join("main.ts")),
+            follow_imports: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        std::fs::remove_dir_all(&dir).unwrap();
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_follow_imports_with_import_references() {
+        let dir = std::env::temp_dir().join(format!(
+            "auto-js-doc-follow-imports-{}-import-references",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("user.ts"), "export type Name = string;\n").unwrap();
+
+        let source_code = r#"
+            import { Name as N } from "./user";
+            function a(name: N) {
+
+            }
+        "#;
+
+        let expected_output = r#"
+            import { Name as N } from "./user";
+            /**
+             * a
+             *
+             * @param {import("./user").Name} name - 
+             */
+            function a(name: N) {
+
+            }
+        "#;
+
+        let options = Options {
+            type_references: TypeReferences::Import,
+            source_path: Some(dir.join("main.ts")),
+            follow_imports: true,
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        std::fs::remove_dir_all(&dir).unwrap();
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_whitespace_is_kept() {
+        let source_code = r#"
+            // not a description
+
+            // a
+            function a() {}
+            class B {
+                // b
+                b() {}
+
+                // c
+
+                c() {}
+            }
+        "#;
+
+        let expected_output = r#"
+            // not a description
+
+            /**
+             * a
+             */
+            function a() {}
+            class B {
+                /**
+                 * b
+                 */
+                b() {}
+
+                /**
+                 * c
+                 */
+                c() {}
+            }
+        "#;
+
+        let updated_code = process(source_code);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_line_ranges() {
+        let source_code = r#"
+            function a() {}
+
+            class B {
+                c() {}
+
+                d(
+                    e: string,
+                ) {}
+            }
+        "#;
+
+        let expected_output = r#"
+            function a() {}
+
+            class B {
+                c() {}
+
+                /**
+                 * d
+                 *
+                 * @param {string} e - 
+                 */
+                d(
+                    e: string,
+                ) {}
+            }
+        "#;
+
+        let options = Options {
+            line_ranges: vec![8..=8, 12..=20],
+            ..Options::default()
+        };
+        let updated_code = process_with_options(source_code, &options);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+    }
+
+    #[test]
+    fn test_document_at() {
+        let source_code = r#"
+            function a() {}
+
+            export class B {
+                // Does c
+                private c(d: string) {
+                    function e() {}
+                }
+            }
+        "#;
+
+        let expected_output = r#"
+            function a() {}
+
+            export class B {
+                /**
+                 * Does c
+                 *
+                 * @param {string} d - 
+                 */
+                private c(d: string) {
+                    function e() {}
+                }
+            }
+        "#;
+
+        let options = Options {
+            public_only: true,
+            ..Options::default()
+        };
+        let documenter = Documenter::new().options(options);
+        let cursor = Position {
+            line: 6,
+            column: 19,
+        };
+        let function = documenter
+            .document_at(source_code, cursor.offset(source_code))
+            .unwrap();
+        assert_eq!(function.name, "c");
+
+        let updated_code = apply_edits(source_code, &function.edits);
+        println!("{}", updated_code);
+        assert_eq!(updated_code, expected_output);
+
+        // The innermost function wins
+        let offset = source_code.find("function e").unwrap() + 10;
+        let function = documenter.document_at(source_code, offset).unwrap();
+        assert_eq!(function.name, "e");
+
+        assert_eq!(documenter.document_at(source_code, 0), None);
+    }
+
+    #[test]
+    fn test_documenter() {
+        let source_code = r#"
+            // Adds two numbers
+            export function add(a: number, b = "1"): number {
+                return a + b;
+            }
+        "#;
+
+        let options = Options {
+            exported_only: true,
+            ..Options::default()
+        };
+        let documenter = Documenter::new().options(options.clone());
+        let documentation = documenter.document(source_code);
+
+        let start = source_code.find("export").unwrap();
+        let comment = source_code.find("//").unwrap();
+        assert_eq!(
+            documentation.edits,
+            vec![
+                Edit::delete(comment..start),
+                Edit::insert(
+                    start,
+                    "/**\n             * Adds two numbers\n             *\n             * @param {number} a - \n             * @param {unknown} b=\"1\" - \n             * @returns {number} \n             */\n            "
+                ),
+            ]
+        );
+        assert_eq!(
+            documentation.functions,
+            vec![FunctionDoc {
+                name: "add".to_owned(),
+                range: start..source_code.rfind('}').unwrap() + 1,
+                description: "Adds two numbers".to_owned(),
+                params: vec![
+                    ParamDoc {
+                        name: "a".to_owned(),
+                        param_type: Some("number".to_owned()),
+                        ..ParamDoc::default()
+                    },
+                    ParamDoc {
+                        name: "b".to_owned(),
+                        default: Some("1".to_owned()),
+                        ..ParamDoc::default()
+                    },
+                ],
+                returns: Some("number".to_owned()),
+                edits: documentation.edits.clone(),
+                ..FunctionDoc::default()
+            }]
+        );
+        assert_eq!(
+            documenter.process(source_code),
+            process_with_options(source_code, &options)
+        );
+    }
+
+    #[test]
+    fn test_documented_source_has_no_edits() {
+        let source_code = r#"
+            // Adds two numbers
+            export function add(a: number, b?: string): number {
+                return a + b;
+            }
+            class C {
+                async d(e: number) {}
+            }
+        "#;
+
+        let documented = process(source_code);
+        let documentation = Documenter::new().document(&documented);
+        assert_eq!(documentation.edits, vec![]);
+        assert_eq!(documentation.functions.len(), 2);
+        assert!(documentation
+            .functions
+            .iter()
+            .all(|function| function.existing_doc.is_some() && function.edits.is_empty()));
+        assert_eq!(process(&documented), documented);
+    }
 }
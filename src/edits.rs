@@ -0,0 +1,185 @@
+use serde::Serialize;
+use std::ops::Range;
+
+/// A change to the source, replacing the text in `range` (empty for an insert)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl Edit {
+    pub fn insert(at: usize, text: &str) -> Edit {
+        Edit {
+            range: at..at,
+            replacement: text.to_owned(),
+        }
+    }
+
+    pub fn delete(range: Range<usize>) -> Edit {
+        Edit {
+            range,
+            replacement: String::new(),
+        }
+    }
+
+    pub fn replace(range: Range<usize>, text: &str) -> Edit {
+        Edit {
+            range,
+            replacement: text.to_owned(),
+        }
+    }
+
+    /// Combine edits into one covering all of them, for clients that don't allow edits to touch
+    pub fn merge(source_code: &str, edits: &[Edit]) -> Option<Edit> {
+        let start = edits.iter().map(|edit| edit.range.start).min()?;
+        let end = edits.iter().map(|edit| edit.range.end).max()?;
+        let shifted = edits
+            .iter()
+            .map(|edit| {
+                Edit::replace(
+                    edit.range.start - start..edit.range.end - start,
+                    &edit.replacement,
+                )
+            })
+            .collect::<Vec<_>>();
+        Some(Edit::replace(
+            start..end,
+            &apply_edits(&source_code[start..end], &shifted),
+        ))
+    }
+
+    /// Add the line and column of the range in the source
+    pub fn locate(&self, source_code: &str) -> LocatedEdit {
+        LocatedEdit {
+            range: self.range.clone(),
+            start: Position::of(source_code, self.range.start),
+            end: Position::of(source_code, self.range.end),
+            replacement: self.replacement.clone(),
+        }
+    }
+}
+
+/// A zero based line and column, with the column counted in UTF-16 code units as editors do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// The position of a byte offset in the source
+    pub fn of(source_code: &str, byte: usize) -> Position {
+        let before = &source_code[..byte];
+        let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+        Position {
+            line: before.matches('\n').count(),
+            column: before[line_start..].encode_utf16().count(),
+        }
+    }
+
+    /// The byte offset of the position in the source, clamped to the end of its line
+    pub fn offset(&self, source_code: &str) -> usize {
+        let mut line_start = 0;
+        for _ in 0..self.line {
+            match source_code[line_start..].find('\n') {
+                Some(n) => line_start += n + 1,
+                None => return source_code.len(),
+            }
+        }
+
+        let mut units = 0;
+        for (i, c) in source_code[line_start..].char_indices() {
+            if c == '\n' || units >= self.column {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+        source_code.len()
+    }
+}
+
+/// An edit with its range as both bytes and lines and columns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LocatedEdit {
+    pub range: Range<usize>,
+    pub start: Position,
+    pub end: Position,
+    pub replacement: String,
+}
+
+/// Write the edits as a JSON array for other tools to apply
+pub fn edits_to_json(source_code: &str, edits: &[Edit]) -> String {
+    let located = edits
+        .iter()
+        .map(|edit| edit.locate(source_code))
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&located).unwrap()
+}
+
+/// Apply non overlapping edits to the source, edits at the same position are applied in the order given
+pub fn apply_edits(source_code: &str, edits: &[Edit]) -> String {
+    let mut sorted = edits.iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|edit| edit.range.start);
+
+    let mut updated_code = String::with_capacity(source_code.len());
+    let mut last_byte = 0;
+    for edit in sorted {
+        updated_code.push_str(&source_code[last_byte..edit.range.start]);
+        updated_code.push_str(&edit.replacement);
+        last_byte = edit.range.end;
+    }
+    updated_code.push_str(&source_code[last_byte..]);
+    updated_code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_edits() {
+        let edits = [
+            Edit::replace(8..9, "c"),
+            Edit::insert(0, "/** a */\n"),
+            Edit::insert(0, "// b\n"),
+            Edit::delete(4..5),
+        ];
+        assert_eq!(
+            apply_edits("let a = b;", &edits),
+            "/** a */\n// b\nlet  = c;"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let source_code = "let a = b;";
+        let edits = [Edit::insert(4, "/* a */ "), Edit::replace(4..5, "c")];
+        let merged = Edit::merge(source_code, &edits).unwrap();
+        assert_eq!(merged, Edit::replace(4..5, "/* a */ c"));
+        assert_eq!(Edit::merge(source_code, &[]), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        let source_code = "a\n  é = b;";
+        let edit = Edit::replace(9..10, "c").locate(source_code);
+        assert_eq!(edit.start, Position { line: 1, column: 6 });
+        assert_eq!(edit.end, Position { line: 1, column: 7 });
+
+        assert_eq!(edit.start.offset(source_code), 9);
+        assert_eq!(Position { line: 0, column: 9 }.offset(source_code), 1);
+
+        let json = edits_to_json(source_code, &[Edit::insert(0, "x")]);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "range": { "start": 0, "end": 0 },
+                "start": { "line": 0, "column": 0 },
+                "end": { "line": 0, "column": 0 },
+                "replacement": "x"
+            }])
+        );
+    }
+}
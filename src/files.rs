@@ -0,0 +1,188 @@
+use auto_js_doc::{map_files, Diagnostic, Documentation, Documenter, FileCoverage, Options};
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::cache::Cache;
+
+// The extensions of the files documented
+const EXTENSIONS: [&str; 6] = ["ts", "mts", "cts", "js", "mjs", "cjs"];
+
+/// A file read from disk and documented by [`document_files`]
+#[derive(Debug, Clone)]
+pub struct DocumentedFile {
+    pub path: PathBuf,
+    pub source_code: String,
+    pub documentation: Documentation,
+    /// Whether the file was up to date in the cache, leaving `documentation` empty
+    pub cached: bool,
+    /// The problems with the existing JsDoc blocks, when asked to `validate` them
+    pub validation: Vec<Diagnostic>,
+}
+
+/// The files to document for the paths, searching directories for `.ts` and `.js` files
+///
+/// Files in `node_modules` and hidden directories are skipped, files named directly are always kept.
+/// Directory entries are sorted so the order is the same on every run.
+pub fn source_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            add_dir(path, path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+fn add_dir(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            eprintln!("Error reading {}: {}", dir.display(), e);
+            return;
+        }
+    };
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            if is_searched(root, &path) {
+                add_dir(root, &path, files);
+            }
+        } else if is_source(root, &path) {
+            files.push(path);
+        }
+    }
+}
+
+// A source file outside of dependencies and hidden directories under the directory
+pub(crate) fn is_source(dir: &Path, path: &Path) -> bool {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    extension.is_some_and(|extension| EXTENSIONS.contains(&extension)) && is_searched(dir, path)
+}
+
+fn is_searched(dir: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    !relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name == "node_modules" || name.starts_with('.')
+    })
+}
+
+/// Read and document the files on a pool of `jobs` threads, each with its own options and the one
+/// based line ranges to document, all of its declarations when there are none
+///
+/// The results are in the order the files were given, whichever thread finished first. Files the
+/// cache has as up to date are read but not parsed.
+pub fn document_files(
+    files: Vec<(PathBuf, Options, Vec<RangeInclusive<usize>>)>,
+    jobs: usize,
+    cache: Option<&Cache>,
+    validate: bool,
+) -> Vec<Result<DocumentedFile, (PathBuf, io::Error)>> {
+    let files = files
+        .into_iter()
+        .map(|(path, options, line_ranges)| (path, (options, line_ranges)))
+        .collect();
+    map_files(files, jobs, |path, source_code, (options, line_ranges)| {
+        if cache.is_some_and(|cache| cache.is_up_to_date(path, &source_code)) {
+            return DocumentedFile {
+                path: path.to_owned(),
+                source_code,
+                documentation: Documentation::default(),
+                cached: true,
+                validation: Vec::new(),
+            };
+        }
+        let documenter = Documenter::new().options(options.clone());
+        let documentation = match line_ranges.is_empty() {
+            true => documenter.document(&source_code),
+            false => documenter.document_lines(&source_code, line_ranges),
+        };
+        let validation = match validate {
+            true => documenter.validate(&source_code),
+            false => Vec::new(),
+        };
+        DocumentedFile {
+            path: path.to_owned(),
+            source_code,
+            documentation,
+            cached: false,
+            validation,
+        }
+    })
+}
+
+/// Read the files and find their declarations for the coverage report, like [`document_files`]
+pub fn coverage_files(
+    files: Vec<(PathBuf, Options)>,
+    jobs: usize,
+) -> Vec<Result<FileCoverage, (PathBuf, io::Error)>> {
+    map_files(files, jobs, |path, source_code, options| FileCoverage {
+        path: path.display().to_string(),
+        symbols: Documenter::new()
+            .options(options.clone())
+            .coverage(&source_code),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_source() {
+        let dir = Path::new("/home/a/.projects/b");
+        assert!(is_source(dir, &dir.join("src/a.ts")));
+        assert!(is_source(dir, &dir.join("src/a.mjs")));
+        assert!(!is_source(dir, &dir.join("src/a.rs")));
+        assert!(!is_source(dir, &dir.join("node_modules/a/index.js")));
+        assert!(!is_source(dir, &dir.join(".git/a.ts")));
+    }
+
+    #[test]
+    fn test_document_files() {
+        let dir = std::env::temp_dir().join(format!("auto-js-doc-files-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/node_modules")).unwrap();
+        for i in 0..20 {
+            let source_code = format!("function f{}(a: number) {{}}\n", i);
+            fs::write(dir.join(format!("src/{:02}.ts", i)), source_code).unwrap();
+        }
+        fs::write(dir.join("src/readme.md"), "").unwrap();
+        fs::write(dir.join("src/node_modules/a.js"), "").unwrap();
+
+        let mut paths = source_files(&[dir.join("src"), dir.join("missing.ts")]);
+        assert_eq!(paths.len(), 21);
+        assert_eq!(paths[0], dir.join("src/00.ts"));
+
+        let files = paths
+            .drain(..)
+            .map(|path| (path, Options::default(), Vec::new()))
+            .collect::<Vec<_>>();
+        let results = document_files(files.clone(), 4, None, false);
+        assert_eq!(results.len(), 21);
+        for (i, result) in results[..20].iter().enumerate() {
+            let file = result.as_ref().unwrap();
+            assert_eq!(file.path, dir.join(format!("src/{:02}.ts", i)));
+            assert_eq!(file.documentation.functions[0].name, format!("f{}", i));
+        }
+        assert!(results[20].is_err());
+
+        let mut cache = Cache::open(&dir.join("cache"), &Options::default());
+        let source_code = fs::read_to_string(dir.join("src/03.ts")).unwrap();
+        cache.insert(&dir.join("src/03.ts"), &source_code);
+        let results = document_files(files, 1, Some(&cache), false);
+        let file = results[3].as_ref().unwrap();
+        assert!(file.cached);
+        assert!(file.documentation.functions.is_empty());
+        let cached = results[4].as_ref().unwrap().cached;
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!cached);
+    }
+}
@@ -0,0 +1,176 @@
+/// Built in verb rules, `{}` is replaced with the rest of the name
+const VERB_RULES: &[(&str, &str)] = &[
+    ("get", "Gets the {}."),
+    ("set", "Sets the {}."),
+    ("is", "Checks whether {}."),
+    ("has", "Checks whether it has {}."),
+    ("can", "Checks whether it can {}."),
+    ("should", "Checks whether it should {}."),
+    ("create", "Creates a new {}."),
+    ("build", "Builds the {}."),
+    ("update", "Updates the {}."),
+    ("delete", "Deletes the {}."),
+    ("remove", "Removes the {}."),
+    ("add", "Adds the {}."),
+    ("find", "Finds the {}."),
+    ("fetch", "Fetches the {}."),
+    ("load", "Loads the {}."),
+    ("save", "Saves the {}."),
+    ("parse", "Parses the {}."),
+    ("validate", "Validates the {}."),
+    ("handle", "Handles the {}."),
+    ("on", "Handles the {} event."),
+    ("to", "Converts to {}."),
+    ("render", "Renders the {}."),
+    ("init", "Initializes the {}."),
+    ("reset", "Resets the {}."),
+];
+
+/// Split a camelCase, PascalCase or snake_case identifier into lowercase words
+pub fn split_identifier(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let chars: Vec<char> = name.trim_start_matches('#').chars().collect();
+
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '_' || *c == '-' || *c == '$' {
+            if !current.is_empty() {
+                words.push(current.to_lowercase());
+                current.clear();
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            // Start a new word at `userId` -> `user|Id` and at the end of acronyms `HTTPServer` -> `HTTP|Server`
+            if prev.is_lowercase() || prev.is_ascii_digit() || next_is_lower {
+                words.push(current.to_lowercase());
+                current.clear();
+            }
+        }
+        current.push(*c);
+    }
+
+    if !current.is_empty() {
+        words.push(current.to_lowercase());
+    }
+
+    words
+}
+
+/// Turn a function name into a sentence using the given verb rules before the built in ones
+pub fn describe_function(name: &str, verb_rules: &[(String, String)]) -> String {
+    let words = split_identifier(name);
+    let Some((verb, rest)) = words.split_first() else {
+        return name.to_owned();
+    };
+
+    if !rest.is_empty() {
+        let custom = verb_rules
+            .iter()
+            .map(|(verb, template)| (verb.as_str(), template.as_str()));
+        let template = custom
+            .chain(VERB_RULES.iter().copied())
+            .find(|(rule_verb, _)| rule_verb.eq_ignore_ascii_case(verb))
+            .map(|(_, template)| template);
+
+        if let Some(template) = template {
+            return template.replace("{}", &rest.join(" "));
+        }
+    }
+
+    sentence(&words.join(" "))
+}
+
+/// Describe a class constructor
+pub fn describe_constructor(class_name: Option<&str>) -> String {
+    match class_name {
+        Some(class_name) => format!("Creates an instance of {}.", class_name),
+        None => "Creates an instance.".to_owned(),
+    }
+}
+
+/// Name the `@callback` type for a function typed parameter after the function or callback it belongs
+/// to, so two functions can each have an `onDone` (`fetchUser`, `onDone` -> `FetchUserOnDoneCallback`)
+pub fn callback_name(scope: &str, param_name: &str) -> String {
+    let scope = scope.strip_suffix("Callback").unwrap_or(scope);
+    let name = split_identifier(scope)
+        .iter()
+        .chain(&split_identifier(param_name))
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => format!("{}{}", first.to_uppercase(), chars.as_str()),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+
+    if name.ends_with("Callback") {
+        name
+    } else {
+        format!("{}Callback", name)
+    }
+}
+
+// Capitalize the first letter and end with a full stop
+fn sentence(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(split_identifier("getUserById"), ["get", "user", "by", "id"]);
+        assert_eq!(split_identifier("UserService"), ["user", "service"]);
+        assert_eq!(split_identifier("load_all_users"), ["load", "all", "users"]);
+        assert_eq!(
+            split_identifier("parseHTTPResponse"),
+            ["parse", "http", "response"]
+        );
+        assert_eq!(split_identifier("#secret"), ["secret"]);
+    }
+
+    #[test]
+    fn test_describe_function() {
+        assert_eq!(
+            describe_function("getUserById", &[]),
+            "Gets the user by id."
+        );
+        assert_eq!(describe_function("isValid", &[]), "Checks whether valid.");
+        assert_eq!(describe_function("testNoExport", &[]), "Test no export.");
+        assert_eq!(describe_function("get", &[]), "Get.");
+
+        let rules = vec![("get".to_owned(), "Returns the {}.".to_owned())];
+        assert_eq!(describe_function("get_user", &rules), "Returns the user.");
+    }
+
+    #[test]
+    fn test_callback_name() {
+        assert_eq!(
+            callback_name("fetchUser", "onDone"),
+            "FetchUserOnDoneCallback"
+        );
+        assert_eq!(callback_name("load", "callback"), "LoadCallback");
+        assert_eq!(callback_name("load", "error_callback"), "LoadErrorCallback");
+        assert_eq!(
+            callback_name("LoadOnDoneCallback", "next"),
+            "LoadOnDoneNextCallback"
+        );
+    }
+
+    #[test]
+    fn test_describe_constructor() {
+        assert_eq!(describe_constructor(Some("A")), "Creates an instance of A.");
+        assert_eq!(describe_constructor(None), "Creates an instance.");
+    }
+}
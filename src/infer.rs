@@ -0,0 +1,445 @@
+use tree_sitter::Node;
+
+/// Node kinds that start a new function scope, their returns and yields belong to them
+const FUNCTION_KINDS: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "function_expression",
+    "function",
+    "generator_function",
+    "arrow_function",
+    "method_definition",
+    "class_declaration",
+    "class",
+];
+
+/// Types inferred from a function without a return type annotation
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct InferredTypes {
+    pub returns: Option<String>,
+    pub yields: Option<String>,
+}
+
+/// Infer the return and yield types of a function from its body
+pub fn infer_types(source_code: &str, function: &Node) -> InferredTypes {
+    let is_async = has_child(function, "async");
+    let is_generator = is_generator(function);
+
+    let mut returns = Vec::new();
+    let mut yields = Vec::new();
+    if let Some(body) = function.child_by_field_name("body") {
+        collect(&body, &mut returns, &mut yields);
+    }
+
+    let return_types = returns
+        .iter()
+        .map(|r| {
+            r.named_child(0)
+                .map(|e| infer_expression(source_code, function, &e))
+        })
+        .map(|t| t.unwrap_or_else(|| Some("undefined".to_owned())))
+        .collect::<Option<Vec<_>>>()
+        .map(|types| union(&types));
+
+    if is_generator {
+        let yield_types = yields
+            .iter()
+            .map(|y| match y.named_child(0) {
+                _ if has_child(y, "*") => None,
+                Some(e) => infer_expression(source_code, function, &e),
+                None => Some("undefined".to_owned()),
+            })
+            .collect::<Option<Vec<_>>>();
+
+        return InferredTypes {
+            // A generator only has a return type when it returns a value
+            returns: if returns.is_empty() {
+                None
+            } else {
+                return_types
+            },
+            yields: match yield_types {
+                Some(types) if !types.is_empty() => Some(union(&types)),
+                _ => Some("unknown".to_owned()),
+            },
+        };
+    }
+
+    let returns = match (returns.is_empty(), return_types) {
+        (true, _) => Some("void".to_owned()),
+        (false, Some(types)) => Some(types),
+        (false, None) => None,
+    };
+
+    InferredTypes {
+        returns: if is_async {
+            Some(format!(
+                "Promise<{}>",
+                returns.unwrap_or_else(|| "unknown".to_owned())
+            ))
+        } else {
+            returns
+        },
+        yields: None,
+    }
+}
+
+/// Checks if a function is a generator (`function*` or `*method()`)
+pub fn is_generator(function: &Node) -> bool {
+    function.kind() == "generator_function_declaration" || has_child(function, "*")
+}
+
+/// Read the yield and return types from a generator's `Generator<T, TReturn, TNext>` style annotation
+pub fn annotated_generator_types(source_code: &str, return_type: &Node) -> Option<InferredTypes> {
+    let generic = return_type.named_child(0)?;
+    if generic.kind() != "generic_type" {
+        return None;
+    }
+
+    let name = generic
+        .child_by_field_name("name")?
+        .utf8_text(source_code.as_bytes())
+        .unwrap();
+    let has_return_type = match name {
+        "Generator" | "AsyncGenerator" | "Iterator" | "AsyncIterator" => true,
+        "IterableIterator" | "Iterable" | "AsyncIterableIterator" | "AsyncIterable" => false,
+        _ => return None,
+    };
+
+    let type_arguments = generic.child_by_field_name("type_arguments")?;
+    let mut cursor = type_arguments.walk();
+    let arguments = type_arguments
+        .named_children(&mut cursor)
+        .map(|t| t.utf8_text(source_code.as_bytes()).unwrap().to_owned())
+        .collect::<Vec<_>>();
+
+    Some(InferredTypes {
+        yields: arguments.first().cloned(),
+        returns: arguments
+            .get(1)
+            .filter(|t| has_return_type && !matches!(t.as_str(), "void" | "undefined"))
+            .cloned(),
+    })
+}
+
+/// Find the errors a function can throw or reject with, in the order they first appear
+pub fn infer_throws(source_code: &str, function: &Node) -> Vec<String> {
+    let mut errors = Vec::new();
+    if let Some(body) = function.child_by_field_name("body") {
+        collect_throws(source_code, &body, false, &mut errors);
+    }
+    errors
+}
+
+// Collect `throw new X()` and `Promise.reject(new X())`, skipping throws caught by a surrounding `try`
+fn collect_throws(source_code: &str, node: &Node, caught: bool, errors: &mut Vec<String>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if FUNCTION_KINDS.contains(&child.kind()) {
+            continue;
+        }
+
+        match child.kind() {
+            "throw_statement" if !caught => {
+                if let Some(error) = child
+                    .named_child(0)
+                    .and_then(|e| error_type(source_code, &e))
+                {
+                    push_unique(errors, error);
+                }
+            }
+            "call_expression" => {
+                let function = child.child_by_field_name("function");
+                let is_reject = function.is_some_and(|f| {
+                    f.utf8_text(source_code.as_bytes()).unwrap() == "Promise.reject"
+                });
+                let error = child
+                    .child_by_field_name("arguments")
+                    .and_then(|args| args.named_child(0))
+                    .and_then(|e| error_type(source_code, &e));
+                if let (true, Some(error)) = (is_reject, error) {
+                    push_unique(errors, error);
+                }
+            }
+            "try_statement" => {
+                // Only the try block is covered by the catch, the handler and finalizer can still throw
+                let has_handler = child.child_by_field_name("handler").is_some();
+                if let Some(body) = child.child_by_field_name("body") {
+                    collect_throws(source_code, &body, caught || has_handler, errors);
+                }
+                for field in ["handler", "finalizer"] {
+                    if let Some(clause) = child.child_by_field_name(field) {
+                        collect_throws(source_code, &clause, caught, errors);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        collect_throws(source_code, &child, caught, errors);
+    }
+}
+
+// The class of a `new X()` expression
+fn error_type(source_code: &str, expression: &Node) -> Option<String> {
+    if expression.kind() != "new_expression" {
+        return None;
+    }
+    expression
+        .child_by_field_name("constructor")
+        .map(|c| c.utf8_text(source_code.as_bytes()).unwrap().to_owned())
+}
+
+fn push_unique(items: &mut Vec<String>, item: String) {
+    if !items.contains(&item) {
+        items.push(item);
+    }
+}
+
+// Collect the return statements and yield expressions belonging to the function
+fn collect<'a>(node: &Node<'a>, returns: &mut Vec<Node<'a>>, yields: &mut Vec<Node<'a>>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if FUNCTION_KINDS.contains(&child.kind()) {
+            continue;
+        }
+        if child.kind() == "return_statement" {
+            returns.push(child);
+        }
+        if child.kind() == "yield_expression" {
+            yields.push(child);
+        }
+        collect(&child, returns, yields);
+    }
+}
+
+// Infer the type of a returned or yielded expression
+fn infer_expression(source_code: &str, function: &Node, expression: &Node) -> Option<String> {
+    let text = |node: &Node| node.utf8_text(source_code.as_bytes()).unwrap().to_owned();
+
+    match expression.kind() {
+        "string" | "template_string" => Some("string".to_owned()),
+        "number" => Some("number".to_owned()),
+        "true" | "false" => Some("boolean".to_owned()),
+        "null" => Some("null".to_owned()),
+        "undefined" => Some("undefined".to_owned()),
+        "object" => Some("Object".to_owned()),
+        "array" => Some("Array".to_owned()),
+        "parenthesized_expression" => expression
+            .named_child(0)
+            .and_then(|inner| infer_expression(source_code, function, &inner)),
+        "as_expression" | "satisfies_expression" => expression.named_child(1).map(|t| text(&t)),
+        "new_expression" => {
+            let constructor = expression.child_by_field_name("constructor")?;
+            let type_arguments = expression
+                .child_by_field_name("type_arguments")
+                .map(|t| text(&t))
+                .unwrap_or_default();
+            Some(format!("{}{}", text(&constructor), type_arguments))
+        }
+        "unary_expression" => {
+            let operator = expression.child_by_field_name("operator").map(|o| text(&o));
+            match operator.as_deref() {
+                Some("!") => Some("boolean".to_owned()),
+                Some("typeof") => Some("string".to_owned()),
+                Some("void") => Some("undefined".to_owned()),
+                _ => None,
+            }
+        }
+        "binary_expression" => {
+            let operator = expression.child_by_field_name("operator").map(|o| text(&o));
+            match operator.as_deref() {
+                Some(
+                    "===" | "!==" | "==" | "!=" | "<" | ">" | "<=" | ">=" | "instanceof" | "in",
+                ) => Some("boolean".to_owned()),
+                _ => None,
+            }
+        }
+        "identifier" => parameter_type(source_code, function, &text(expression)),
+        _ => None,
+    }
+}
+
+// Look up the annotated type of a parameter by name, for functions returning one of their params
+fn parameter_type(source_code: &str, function: &Node, name: &str) -> Option<String> {
+    let parameters = function.child_by_field_name("parameters")?;
+    let mut cursor = parameters.walk();
+    let parameter = parameters.named_children(&mut cursor).find(|param| {
+        param
+            .child_by_field_name("pattern")
+            .is_some_and(|pattern| pattern.utf8_text(source_code.as_bytes()).unwrap() == name)
+    })?;
+
+    parameter
+        .child_by_field_name("type")
+        .and_then(|t| t.named_child(0))
+        .map(|t| t.utf8_text(source_code.as_bytes()).unwrap().to_owned())
+}
+
+// Join types into a union, dropping duplicates
+fn union(types: &[String]) -> String {
+    let mut unique: Vec<&str> = Vec::new();
+    for t in types {
+        if !unique.contains(&t.as_str()) {
+            unique.push(t);
+        }
+    }
+    unique.join(" | ")
+}
+
+fn has_child(node: &Node, kind: &str) -> bool {
+    let mut cursor = node.walk();
+    let found = node.children(&mut cursor).any(|child| child.kind() == kind);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+    use tree_sitter_typescript::language_typescript;
+
+    // Run `f` against the first declaration in the source
+    fn with_function<T>(source_code: &str, f: fn(&str, &Node) -> T) -> T {
+        let mut parser = Parser::new();
+        parser.set_language(&language_typescript()).unwrap();
+        let tree = parser.parse(source_code, None).unwrap();
+        let function = tree.root_node().named_child(0).unwrap();
+        f(source_code, &function)
+    }
+
+    fn infer(source_code: &str) -> InferredTypes {
+        with_function(source_code, infer_types)
+    }
+
+    fn throws(source_code: &str) -> Vec<String> {
+        with_function(source_code, infer_throws)
+    }
+
+    #[test]
+    fn test_infer_returns() {
+        assert_eq!(infer("function a() {}").returns.as_deref(), Some("void"));
+        assert_eq!(
+            infer("function a(x) { if (x) return 'a'; return 1; }")
+                .returns
+                .as_deref(),
+            Some("string | number")
+        );
+        assert_eq!(
+            infer("function a(x: Foo) { return x; }").returns.as_deref(),
+            Some("Foo")
+        );
+        assert_eq!(
+            infer("function a() { return new Map<string, number>(); }")
+                .returns
+                .as_deref(),
+            Some("Map<string, number>")
+        );
+        assert_eq!(
+            infer("function a() { return other(); }").returns.as_deref(),
+            None
+        );
+        assert_eq!(
+            infer("function a() { const b = () => { return 1; }; }")
+                .returns
+                .as_deref(),
+            Some("void")
+        );
+    }
+
+    #[test]
+    fn test_infer_async() {
+        assert_eq!(
+            infer("async function a() { return true; }")
+                .returns
+                .as_deref(),
+            Some("Promise<boolean>")
+        );
+        assert_eq!(
+            infer("async function a() {}").returns.as_deref(),
+            Some("Promise<void>")
+        );
+        assert_eq!(
+            infer("async function a() { return await b(); }")
+                .returns
+                .as_deref(),
+            Some("Promise<unknown>")
+        );
+    }
+
+    #[test]
+    fn test_infer_throws() {
+        assert_eq!(
+            throws(
+                r#"function a(x) {
+                    if (!x) throw new ValidationError("x");
+                    if (x > 1) throw new ValidationError("y");
+                    return Promise.reject(new errors.Timeout());
+                }"#
+            ),
+            ["ValidationError", "errors.Timeout"]
+        );
+        assert_eq!(
+            throws(
+                r#"function a() {
+                    try { throw new Caught(); } catch (e) { throw new Rethrown(); }
+                    try { throw new Uncaught(); } finally {}
+                    const b = () => { throw new Nested(); };
+                    throw err;
+                }"#
+            ),
+            ["Rethrown", "Uncaught"]
+        );
+    }
+
+    #[test]
+    fn test_annotated_generator_types() {
+        let annotated = |source_code: &str| {
+            with_function(source_code, |source_code, function| {
+                let return_type = function.child_by_field_name("return_type").unwrap();
+                annotated_generator_types(source_code, &return_type)
+            })
+        };
+
+        assert_eq!(
+            annotated("function* a(): Generator<number, string, boolean> {}"),
+            Some(InferredTypes {
+                returns: Some("string".to_owned()),
+                yields: Some("number".to_owned())
+            })
+        );
+        assert_eq!(
+            annotated("async function* a(): AsyncGenerator<User, void> {}"),
+            Some(InferredTypes {
+                returns: None,
+                yields: Some("User".to_owned())
+            })
+        );
+        assert_eq!(
+            annotated("function* a(): IterableIterator<number> {}"),
+            Some(InferredTypes {
+                returns: None,
+                yields: Some("number".to_owned())
+            })
+        );
+        assert_eq!(annotated("function* a(): Promise<number> {}"), None);
+    }
+
+    #[test]
+    fn test_infer_generators() {
+        assert_eq!(
+            infer("function* a() { yield 1; yield 2; }"),
+            InferredTypes {
+                returns: None,
+                yields: Some("number".to_owned())
+            }
+        );
+        assert_eq!(
+            infer("function* a() { yield* b(); return 'done'; }"),
+            InferredTypes {
+                returns: Some("string".to_owned()),
+                yields: Some("unknown".to_owned())
+            }
+        );
+    }
+}
@@ -1,10 +1,49 @@
+mod blocks;
+mod coverage;
+mod diagnostics;
+mod diff;
+mod directives;
+mod documenter;
 mod e2e_test;
+mod edits;
+mod humanize;
+mod infer;
+mod options;
+#[cfg(not(target_arch = "wasm32"))]
+mod pool;
+mod report;
+mod request;
+mod session;
 mod structs;
-
-use std::io::{self, Read, Write};
+mod symbols;
+mod types;
+mod validate;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use coverage::{format_coverage, Count, FileCoverage, Summary, Symbol, SymbolKind};
+pub use diagnostics::{
+    Diagnostic, MISSING_JSDOC, MISSING_PARAM, OPTIONAL_MISMATCH, OUTDATED_JSDOC, PARAM_ORDER,
+    TYPE_MISMATCH, UNKNOWN_PARAM, VOID_RETURNS,
+};
+pub use diff::parse_unified_diff;
+use directives::Directive;
+pub use documenter::{Documentation, Documenter};
+pub use edits::{apply_edits, edits_to_json, Edit, LocatedEdit, Position};
+pub use options::{DescriptionStyle, Options, ReportFormat};
+#[cfg(not(target_arch = "wasm32"))]
+pub use pool::{default_jobs, map_files};
+pub use report::format_report;
+pub use request::{process_source, ProcessOptions, ProcessResult};
+pub use session::Session;
+use std::ops::Range;
 use structs::JsDoc;
-use tree_sitter::{Node, Parser};
+pub use structs::{FunctionDoc, ParamDoc};
+use symbols::SymbolTable;
+pub use symbols::TypeReferences;
+use tree_sitter::{Node, Parser, Tree};
 use tree_sitter_typescript::language_typescript;
+pub use types::TypeStyle;
 
 #[derive(Debug, Default)]
 struct FunctionInfo {
@@ -20,42 +59,107 @@ impl FunctionInfo {
     }
 }
 
-pub fn main() {
-    // Create a handle to stdin
-    let stdin = io::stdin();
-    let mut handle = stdin.lock();
+/// Add JsDoc blocks to the functions in the source using the default options
+pub fn process(source_code: &str) -> String {
+    process_with_options(source_code, &Options::default())
+}
+
+/// Add JsDoc blocks to the functions in the source
+pub fn process_with_options(source_code: &str, options: &Options) -> String {
+    let documentation = document(source_code, options);
+    apply_edits(source_code, &documentation.edits)
+}
 
-    // Create a string to hold the entire input
-    let mut input = String::new();
+// Work out the edits and signature data for the source
+fn document(source_code: &str, options: &Options) -> Documentation {
+    document_tree(source_code, &parse(source_code), options)
+}
 
-    // Read the entire input into the string
-    let output = match handle.read_to_string(&mut input) {
-        Ok(_) => process(&input),
-        Err(_) => "".to_owned(),
+// Work out the edits and signature data for an already parsed source
+fn document_tree(source_code: &str, tree: &Tree, options: &Options) -> Documentation {
+    let root_node = tree.root_node();
+
+    let mut documentation = Documentation::default();
+    if has_ignore_file_directive(source_code, &root_node) {
+        return documentation;
+    }
+
+    let symbols = symbol_table(source_code, &root_node, options);
+    let mut documenting = Documenting {
+        source_code,
+        options,
+        symbols: &symbols,
+        documentation: &mut documentation,
     };
+    walk(&root_node, source_code, options, &mut documenting);
+    documentation
+}
 
-    // Create a handle to stdout
-    let stdout = io::stdout();
-    let mut handle_out = stdout.lock();
+// Document the innermost function or method containing the byte offset, whatever the filters say
+fn document_at(source_code: &str, options: &Options, offset: usize) -> Option<FunctionDoc> {
+    let tree = parse(source_code);
+    let root_node = tree.root_node();
 
-    // Write the processed input to stdout
-    if let Err(e) = writeln!(handle_out, "{}", output) {
-        eprintln!("Error writing to stdout: {}", e);
+    let mut node = root_node.descendant_for_byte_range(offset, offset)?;
+    while !is_function_declaration(&node) && node.kind() != "method_definition" {
+        node = node.parent()?;
+    }
+    let node = node
+        .parent()
+        .filter(|parent| parent.kind() == "export_statement")
+        .unwrap_or(node);
+
+    // The comments right before it, as `walk` would have collected them
+    let mut comments = Vec::new();
+    let mut previous = node.prev_sibling();
+    while let Some(comment) = previous.filter(|previous| previous.kind() == "comment") {
+        comments.push(comment);
+        previous = comment.prev_sibling();
+    }
+    let mut pending = PendingComments::default();
+    for comment in comments.iter().rev() {
+        pending.add(source_code, comment, options);
     }
 
-    io::stdout().flush().ok();
+    let symbols = symbol_table(source_code, &root_node, options);
+    let mut documentation = Documentation::default();
+    process_functions(
+        source_code,
+        &node,
+        &mut documentation,
+        &pending,
+        options,
+        &symbols,
+    );
+    documentation.functions.pop()
 }
 
-pub fn process(source_code: &str) -> String {
+fn parser() -> Parser {
     let mut parser = Parser::new();
     parser
         .set_language(&language_typescript())
         .expect("Error loading Typescript grammar");
+    parser
+}
 
-    let tree = parser.parse(source_code, None).unwrap();
-    let root_node = tree.root_node();
+fn parse(source_code: &str) -> Tree {
+    parser().parse(source_code, None).unwrap()
+}
 
-    walk(&root_node, source_code)
+// Collect the named types when they are resolved
+fn symbol_table(source_code: &str, root_node: &Node, options: &Options) -> SymbolTable {
+    let mut symbols = SymbolTable::default();
+    if options.type_references != TypeReferences::Keep {
+        symbols = SymbolTable::from_tree(source_code, root_node);
+        // Only inlining needs the aliases behind imports, the other modes keep the imported name
+        let inline = options.type_references == TypeReferences::Inline;
+        if let (true, true, Some(source_path)) =
+            (inline, options.follow_imports, &options.source_path)
+        {
+            symbols.load_imports(source_path);
+        }
+    }
+    symbols
 }
 
 // Returns indentation of a node as a string of the indentation characters
@@ -69,8 +173,171 @@ fn get_indentation(source_code: &str, node: &Node) -> String {
     indentation
 }
 
-// todo
-fn get_params(source_code: &str, child: &Node, js_doc: &mut JsDoc) {
+// A comment written next to a parameter or property
+#[derive(Debug)]
+struct AttachedComment<'a> {
+    item: Node<'a>,
+    comment: Node<'a>,
+    // Written before the item (`/** max retries */ retries`) rather than after it (`timeout, // in ms`)
+    leading: bool,
+}
+
+// Pair up the items (parameters or properties) in a list with the comments written next to them
+fn attach_comments<'a>(list: &Node<'a>) -> Vec<AttachedComment<'a>> {
+    let mut cursor = list.walk();
+    let children = list.named_children(&mut cursor).collect::<Vec<_>>();
+    let items = children
+        .iter()
+        .filter(|child| !is_comment(child))
+        .collect::<Vec<_>>();
+
+    let mut attached = Vec::new();
+    for comment in children.iter().filter(|child| is_comment(child)) {
+        let prev = items
+            .iter()
+            .rev()
+            .find(|item| item.end_byte() <= comment.start_byte());
+        let next = items
+            .iter()
+            .find(|item| item.start_byte() >= comment.end_byte());
+
+        let row = comment.start_position().row;
+        let trails_prev = prev.is_some_and(|prev| prev.end_position().row == row);
+        let leads_next =
+            next.is_some_and(|next| next.start_position().row == comment.end_position().row);
+
+        if trails_prev && !leads_next {
+            attached.push(AttachedComment {
+                item: **prev.unwrap(),
+                comment: *comment,
+                leading: false,
+            });
+        } else if let Some(next) = next {
+            attached.push(AttachedComment {
+                item: **next,
+                comment: *comment,
+                leading: true,
+            });
+        }
+    }
+    attached
+}
+
+// Comments inside interface bodies are reported under the body's kind, so check for extras as well
+fn is_comment(node: &Node) -> bool {
+    node.kind() == "comment" || node.is_extra()
+}
+
+// Join the comments attached to an item into a description
+fn comment_description(source_code: &str, attached: &[AttachedComment], item: &Node) -> String {
+    attached
+        .iter()
+        .filter(|a| a.item.id() == item.id())
+        .map(|a| parse_comment(a.comment.utf8_text(source_code.as_bytes()).unwrap()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Find the properties behind a parameter type, either inline `{ a: string }` or a local interface / type alias
+fn get_type_properties<'a>(source_code: &str, type_node: &Node<'a>) -> Option<Node<'a>> {
+    if type_node.kind() == "object_type" {
+        return Some(*type_node);
+    }
+    if type_node.kind() != "type_identifier" {
+        return None;
+    }
+
+    let type_name = type_node.utf8_text(source_code.as_bytes()).unwrap();
+    let mut root = *type_node;
+    while let Some(parent) = root.parent() {
+        root = parent;
+    }
+
+    let mut cursor = root.walk();
+    let declarations = root.children(&mut cursor).map(|child| {
+        if child.kind() == "export_statement" {
+            child.child_by_field_name("declaration").unwrap_or(child)
+        } else {
+            child
+        }
+    });
+
+    for declaration in declarations.collect::<Vec<_>>() {
+        let name = declaration
+            .child_by_field_name("name")
+            .map(|name| name.utf8_text(source_code.as_bytes()).unwrap());
+        if name != Some(type_name) {
+            continue;
+        }
+        match declaration.kind() {
+            "interface_declaration" => return declaration.child_by_field_name("body"),
+            "type_alias_declaration" => {
+                return declaration
+                    .child_by_field_name("value")
+                    .filter(|value| value.kind() == "object_type")
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Add `@param` lines for the properties of an object parameter when any of them are commented
+fn add_property_params(
+    source_code: &str,
+    param_name: &str,
+    properties: &Node,
+    js_doc: &mut JsDoc,
+    harvested: &mut Vec<(Range<usize>, bool)>,
+) {
+    let attached = attach_comments(properties);
+    if attached.is_empty() {
+        return;
+    }
+
+    let mut cursor = properties.walk();
+    for property in properties.named_children(&mut cursor) {
+        if property.kind() != "property_signature" {
+            continue;
+        }
+        let Some(name) = property.child_by_field_name("name") else {
+            continue;
+        };
+        let property_type = property
+            .child_by_field_name("type")
+            .and_then(|t| t.named_child(0))
+            .map(|t| type_text(source_code, &t));
+        let mut property_cursor = property.walk();
+        let optional = property
+            .children(&mut property_cursor)
+            .any(|child| child.kind() == "?");
+
+        js_doc.add_param(
+            &format!(
+                "{}.{}",
+                param_name,
+                name.utf8_text(source_code.as_bytes()).unwrap()
+            ),
+            property_type,
+            optional,
+            None,
+            &comment_description(source_code, &attached, &property),
+        );
+    }
+
+    harvested.extend(attached.iter().map(|a| (a.comment.byte_range(), a.leading)));
+}
+
+// Adds the params to the JsDoc, returning the comments used as descriptions and whether they lead their item.
+// The callback types of the params are named after the scope, the function or callback they belong to
+fn get_params(
+    source_code: &str,
+    child: &Node,
+    scope: &str,
+    js_doc: &mut JsDoc,
+    options: &Options,
+    callbacks: &mut Vec<String>,
+) -> Vec<(Range<usize>, bool)> {
     // if child.kind() == "export_statement" {
     //     println!(
     //         "here {:?}",
@@ -83,20 +350,27 @@ fn get_params(source_code: &str, child: &Node, js_doc: &mut JsDoc) {
     //     ); // TODO reports as none for exported !!
     // }
 
+    let mut harvested = Vec::new();
+
     if let Some(parameters_node) = child.child_by_field_name("parameters") {
         // If there is more then 1 param add a space under the description
         if parameters_node
             .named_children(&mut parameters_node.walk())
+            .filter(|param| !is_comment(param))
             .count()
             > 0
         {
-            js_doc.add_space();
+            js_doc.start_tags();
         }
 
+        let attached = attach_comments(&parameters_node);
+        harvested.extend(attached.iter().map(|a| (a.comment.byte_range(), a.leading)));
+
         for param in parameters_node.named_children(&mut parameters_node.walk()) {
             let mut param_name: Option<String> = None;
-            let mut param_type: Option<String> = None;
+            let mut type_node: Option<Node> = None;
             let mut param_default: Option<String> = None;
+            let mut properties: Option<Node> = None;
             let param_required = param.kind() == "required_parameter";
 
             for child in param.named_children(&mut param.walk()) {
@@ -105,14 +379,8 @@ fn get_params(source_code: &str, child: &Node, js_doc: &mut JsDoc) {
                     param_name = Some(child.utf8_text(source_code.as_bytes()).unwrap().to_owned());
                 }
                 if child.kind() == "type_annotation" {
-                    if let Some(type_node) = child.named_child(0) {
-                        param_type = Some(
-                            type_node
-                                .utf8_text(source_code.as_bytes())
-                                .unwrap()
-                                .to_owned(),
-                        );
-                    }
+                    type_node = child.named_child(0);
+                    properties = type_node.and_then(|t| get_type_properties(source_code, &t));
                 }
 
                 // Get any default value assigned
@@ -134,182 +402,564 @@ fn get_params(source_code: &str, child: &Node, js_doc: &mut JsDoc) {
             //     param_name, param_type, param_default
             // );
 
-            if let (Some(param_name), param_type) = (param_name.as_ref(), param_type.clone()) {
+            if let (Some(param_name), type_node) = (param_name.as_ref(), type_node) {
+                let param_type = type_node.map(|type_node| {
+                    if options.callback_typedefs && type_node.kind() == "function_type" {
+                        let name = humanize::callback_name(scope, param_name);
+                        harvested.extend(build_callback(
+                            source_code,
+                            &type_node,
+                            &name,
+                            js_doc,
+                            options,
+                            callbacks,
+                        ));
+                        name
+                    } else {
+                        type_text(source_code, &type_node)
+                    }
+                });
+
                 js_doc.add_param(
                     param_name,
                     param_type,
                     !param_required,
                     param_default.clone(),
-                    "",
+                    &comment_description(source_code, &attached, &param),
                 );
+
+                if let Some(properties) = properties {
+                    add_property_params(
+                        source_code,
+                        param_name,
+                        &properties,
+                        js_doc,
+                        &mut harvested,
+                    );
+                }
             }
         }
     }
+
+    harvested
 }
 
-fn walk(node: &Node, source_code: &str) -> String {
-    let mut cursor = node.walk();
-    let mut updated_code = String::new();
-    let mut last_byte = 0;
+// Adds a `@callback` block for a function type to the callbacks, returning the comments used as descriptions
+fn build_callback(
+    source_code: &str,
+    function_type: &Node,
+    name: &str,
+    parent_doc: &JsDoc,
+    options: &Options,
+    callbacks: &mut Vec<String>,
+) -> Vec<(Range<usize>, bool)> {
+    let mut callback_doc = parent_doc.new_like();
+    callback_doc.add_callback(name);
+
+    let harvested = get_params(
+        source_code,
+        function_type,
+        name,
+        &mut callback_doc,
+        options,
+        callbacks,
+    );
+    if let Some(return_type) = function_type.child_by_field_name("return_type") {
+        callback_doc.add_return(&type_text(source_code, &return_type), "");
+    }
 
-    let mut comment: Option<String> = None;
+    callbacks.push(callback_doc.build());
+    harvested
+}
 
-    for child in node.children(&mut cursor) {
-        let child_start_byte = child.start_byte();
-        let child_end_byte = child.end_byte();
+// Returns the text of a type without the comments inside it, so they can't close the JsDoc early
+fn type_text(source_code: &str, node: &Node) -> String {
+    let mut comments = Vec::new();
+    let mut stack = vec![*node];
+    while let Some(current) = stack.pop() {
+        if is_comment(&current) && current.id() != node.id() {
+            comments.push((current.byte_range(), false));
+            continue;
+        }
+        let mut cursor = current.walk();
+        stack.extend(current.children(&mut cursor));
+    }
 
-        // Append the text from the end of the last child to the start of the current child
-        // updated_code.push_str(&source_code[last_byte..child_start_byte]);
-        // Get the text between the last child and the current child
-        let text_between = &source_code[last_byte..child_start_byte];
+    strip_comments(source_code, node, &comments)
+}
 
-        if child.kind() == "comment" {
-            // updated_code.push('\n');
-            comment = Some(parse_comment(
-                child.utf8_text(source_code.as_bytes()).unwrap(),
-            ));
-        } else {
-            updated_code.push_str(text_between);
-            if child.kind() == "export_statement" || child.kind() == "function_declaration" {
-                process_functions(source_code, &child, &mut updated_code, &comment);
-            } else if child.kind() == "class_declaration" {
-                process_class_declaration(source_code, &child, &mut updated_code);
+// Returns the text of a node with the given comments taken out, along with any whitespace they leave behind
+fn strip_comments(source_code: &str, node: &Node, comments: &[(Range<usize>, bool)]) -> String {
+    let range = node.byte_range();
+    let mut removals = comments
+        .iter()
+        .filter(|(comment, _)| range.start <= comment.start && comment.end <= range.end)
+        .map(|(comment, leading)| {
+            let line_start = source_code[..comment.start]
+                .rfind('\n')
+                .map_or(0, |n| n + 1);
+            let blank_before = source_code[line_start..comment.start].trim().is_empty();
+            let blank_after = source_code[comment.end..]
+                .trim_start_matches([' ', '\t'])
+                .starts_with('\n');
+
+            if blank_before && blank_after && line_start > range.start {
+                // The comment sits on its own line, so drop the whole line
+                let line_end = comment.end + source_code[comment.end..].find('\n').unwrap();
+                line_start - 1..line_end
+            } else if *leading {
+                let spaces = source_code[comment.end..].len()
+                    - source_code[comment.end..]
+                        .trim_start_matches([' ', '\t'])
+                        .len();
+                comment.start..comment.end + spaces
             } else {
-                updated_code.push_str(child.utf8_text(source_code.as_bytes()).unwrap());
+                let spaces = source_code[..comment.start].len()
+                    - source_code[..comment.start]
+                        .trim_end_matches([' ', '\t'])
+                        .len();
+                comment.start - spaces..comment.end
             }
+        })
+        .collect::<Vec<_>>();
+    removals.sort_by_key(|removal| removal.start);
+
+    let mut text = String::new();
+    let mut last_byte = range.start;
+    for removal in removals {
+        if removal.start >= last_byte {
+            text.push_str(&source_code[last_byte..removal.start]);
+            last_byte = removal.end;
         }
+    }
+    text.push_str(&source_code[last_byte..range.end]);
+    text
+}
 
-        // Update last_byte to the end of the current child
-        last_byte = child_end_byte;
+// Comments seen since the last declaration, held until we know if the next one gets documented
+#[derive(Debug, Default)]
+struct PendingComments {
+    // From the description comment to the start of whatever follows it
+    description_range: Option<(usize, usize)>,
+    description: Option<String>,
+    // The description comment when it is an existing JsDoc block
+    doc_block: Option<Range<usize>>,
+    // Previously generated `@callback` blocks, which get generated again
+    callback_ranges: Vec<(usize, usize)>,
+    ignore_next: bool,
+    // The line of the first comment, as changing any of them can change the JsDoc
+    first_row: Option<usize>,
+}
+
+impl PendingComments {
+    // Record a comment, returning any directive it holds
+    fn add(&mut self, source_code: &str, node: &Node, options: &Options) -> Option<Directive> {
+        // Take the whitespace after the comment with it, so its line goes once the comment moves into the JsDoc
+        let end = node.end_byte()
+            + source_code[node.end_byte()..]
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(source_code.len() - node.end_byte());
+
+        self.first_row.get_or_insert(node.start_position().row);
+
+        let text = node.utf8_text(source_code.as_bytes()).unwrap();
+        let directive = Directive::parse(text);
+        match directive {
+            Some(Directive::Ignore) => self.ignore_next = true,
+            Some(_) => {}
+            None if options.callback_typedefs && text.contains("@callback") => {
+                self.callback_ranges.push((node.start_byte(), end));
+            }
+            None => {
+                self.description_range = Some((node.start_byte(), end));
+                self.description = Some(parse_comment(text));
+                self.doc_block = text.starts_with("/**").then(|| node.byte_range());
+            }
+        }
+        directive
     }
 
-    // Append any remaining text after the last child
-    updated_code.push_str(&source_code[last_byte..]);
+    // Remove the comments that move into the JsDoc
+    fn remove_description(&self, edits: &mut Vec<Edit>) {
+        let mut replaced = self.callback_ranges.clone();
+        replaced.extend(self.description_range);
+        replaced.sort();
 
-    updated_code
+        for (before, after) in replaced {
+            edits.push(Edit::delete(before..after));
+        }
+    }
 }
 
-fn process_class_declaration(source_code: &str, node: &Node, updated_code: &mut String) {
-    let mut inner_cursor = node.walk();
-    let start_byte = node.start_byte();
-    let mut last_byte = start_byte;
+// What `walk` finds, so documenting and coverage go through the same declarations
+trait Visitor {
+    // A function or method that passes the filters, `exported` when declared with `export`
+    fn function(&mut self, node: &Node, pending: &PendingComments, exported: bool);
+
+    // A class, before its members
+    fn class(&mut self, _node: &Node, _pending: &PendingComments, _exported: bool) {}
+}
+
+// Adds the JsDoc blocks of the functions and methods to the documentation
+struct Documenting<'a> {
+    source_code: &'a str,
+    options: &'a Options,
+    symbols: &'a SymbolTable,
+    documentation: &'a mut Documentation,
+}
+
+impl Visitor for Documenting<'_> {
+    fn function(&mut self, node: &Node, pending: &PendingComments, _exported: bool) {
+        process_functions(
+            self.source_code,
+            node,
+            self.documentation,
+            pending,
+            self.options,
+            self.symbols,
+        );
+    }
+}
+
+fn walk(node: &Node, source_code: &str, options: &Options, visitor: &mut impl Visitor) {
+    let mut cursor = node.walk();
 
-    for child in node.children(&mut inner_cursor) {
-        let child_start_byte = child.start_byte();
-        updated_code.push_str(&source_code[last_byte..child_start_byte]);
+    let mut pending = PendingComments::default();
+    let mut disabled = false;
 
-        if child.kind() == "class_body" {
-            process_class_body(source_code, &child, updated_code);
+    for child in node.children(&mut cursor) {
+        if child.kind() == "comment" {
+            match pending.add(source_code, &child, options) {
+                Some(Directive::Disable) => disabled = true,
+                Some(Directive::Enable) => disabled = false,
+                _ => {}
+            }
         } else {
-            updated_code.push_str(child.utf8_text(source_code.as_bytes()).unwrap());
+            let skip =
+                disabled || pending.ignore_next || !in_line_ranges(&child, &pending, options);
+            let is_function = is_function_declaration(&child) || is_exported_function(&child);
+            let exported = child.kind() == "export_statement";
+
+            if !skip && is_function && should_document(source_code, &child, options, exported) {
+                visitor.function(&child, &pending, exported);
+            } else if !skip && child.kind() == "class_declaration" {
+                // Not documented, so any preceding comments are kept as they were
+                process_class_declaration(source_code, &child, &pending, options, false, visitor);
+            } else if let Some(class) = exported_class(&child).filter(|_| !skip) {
+                process_class_declaration(source_code, &class, &pending, options, true, visitor);
+            }
+            pending = PendingComments::default();
         }
+    }
+}
 
-        last_byte = child.end_byte();
+fn process_class_declaration(
+    source_code: &str,
+    node: &Node,
+    pending: &PendingComments,
+    options: &Options,
+    exported: bool,
+    visitor: &mut impl Visitor,
+) {
+    visitor.class(node, pending, exported);
+    if let Some(body) = node.child_by_field_name("body") {
+        process_class_body(source_code, &body, options, exported, visitor);
     }
-    updated_code.push_str(&source_code[last_byte..node.end_byte()]);
 }
 
-fn process_class_body(source_code: &str, node: &Node, updated_code: &mut String) {
+fn process_class_body(
+    source_code: &str,
+    node: &Node,
+    options: &Options,
+    exported: bool,
+    visitor: &mut impl Visitor,
+) {
     let mut body_cursor = node.walk();
-    let start_byte = node.start_byte();
-    let mut last_byte = start_byte;
 
-    let mut comment: Option<String> = None;
+    let mut pending = PendingComments::default();
+    let mut disabled = false;
 
     for child in node.children(&mut body_cursor) {
-        let child_start_byte = child.start_byte();
+        if child.kind() == "comment" {
+            // Hold on to the comment until we know whether the next member gets documented
+            match pending.add(source_code, &child, options) {
+                Some(Directive::Disable) => disabled = true,
+                Some(Directive::Enable) => disabled = false,
+                _ => {}
+            }
+            continue;
+        }
 
-        // println!(
-        //     "child: {} line: ${:?}",
-        //     child.kind(),
-        //     child.utf8_text(source_code.as_bytes())
-        // );
+        let skip = disabled || pending.ignore_next || !in_line_ranges(&child, &pending, options);
 
-        // Get the text between the last child and the current child
-        let text_between = &source_code[last_byte..child_start_byte];
+        if !skip
+            && child.kind() == "method_definition"
+            && should_document(source_code, &child, options, exported)
+        {
+            visitor.function(&child, &pending, false);
+        } else if !skip && child.kind() == "class_declaration" {
+            process_class_declaration(source_code, &child, &pending, options, exported, visitor);
+        }
+        pending = PendingComments::default();
+    }
+}
 
-        if child.kind() == "comment" {
-            // Skip the comment but handle surrounding whitespace
-            // if let Some(last_newline) = text_between.rfind('\n') {
-            //     updated_code.push_str(&text_between[..=last_newline]);
-            // }
-            updated_code.push('\n');
-
-            // comment = Some("my comment".to_owned());
-            // comment = Some(child.utf8_text(source_code.as_bytes()).unwrap().to_owned());
-            comment = Some(parse_comment(
-                child.utf8_text(source_code.as_bytes()).unwrap(),
-            ));
-
-            // println!("text_between: {:?}", &text_between);
-            last_byte = child.end_byte();
-            continue;
-        } else {
-            updated_code.push_str(text_between);
+// Checks the comments at the top of the file for `auto-js-doc-ignore-file`
+fn has_ignore_file_directive(source_code: &str, root_node: &Node) -> bool {
+    let mut cursor = root_node.walk();
+    let has_directive = root_node
+        .children(&mut cursor)
+        .take_while(|child| child.kind() == "comment")
+        .any(|child| {
+            Directive::parse(child.utf8_text(source_code.as_bytes()).unwrap())
+                == Some(Directive::IgnoreFile)
+        });
+    has_directive
+}
 
-            // println!("class body kind: ${:?}", child.kind());
+// Checks if the node declares a plain or generator function
+fn is_function_declaration(node: &Node) -> bool {
+    node.kind() == "function_declaration" || node.kind() == "generator_function_declaration"
+}
 
-            if child.kind() == "method_definition" {
-                process_functions(source_code, &child, updated_code, &comment);
-            } else if child.kind() == "class_declaration" {
-                process_class_declaration(source_code, &child, updated_code);
-            } else {
-                updated_code.push_str(child.utf8_text(source_code.as_bytes()).unwrap());
-            }
+// Checks if the node is an export statement wrapping a function declaration
+fn is_exported_function(node: &Node) -> bool {
+    node.kind() == "export_statement"
+        && node
+            .child_by_field_name("declaration")
+            .is_some_and(|d| is_function_declaration(&d))
+}
+
+// Returns the class declared by an export statement, if any
+fn exported_class<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    if node.kind() != "export_statement" {
+        return None;
+    }
+    node.child_by_field_name("declaration")
+        .filter(|d| d.kind() == "class_declaration")
+}
+
+// Checks whether the node or the comments before it touch any of the line ranges, when processing
+// is limited to them
+fn in_line_ranges(node: &Node, pending: &PendingComments, options: &Options) -> bool {
+    let start = pending
+        .first_row
+        .unwrap_or(usize::MAX)
+        .min(node.start_position().row)
+        + 1;
+    let end = node.end_position().row + 1;
+    options.line_ranges.is_empty()
+        || options
+            .line_ranges
+            .iter()
+            .any(|range| *range.start() <= end && start <= *range.end())
+}
+
+// Checks a function or method against the filters set in the options
+fn should_document(source_code: &str, node: &Node, options: &Options, exported: bool) -> bool {
+    if options.exported_only && !exported {
+        return false;
+    }
+
+    if let Some(min_lines) = options.min_lines {
+        let lines = node.end_position().row - node.start_position().row + 1;
+        if lines < min_lines {
+            return false;
         }
+    }
 
-        last_byte = child.end_byte();
+    if options.public_only && is_private_member(source_code, node) {
+        return false;
     }
-    updated_code.push_str(&source_code[last_byte..node.end_byte()]);
+
+    let info = get_function_details_from_node(source_code, node);
+
+    if options.skip_constructors
+        && node.kind() == "method_definition"
+        && info.function_name == "constructor"
+    {
+        return false;
+    }
+
+    if let Some(pattern) = &options.ignore_pattern {
+        if pattern.is_match(&info.function_name) {
+            return false;
+        }
+    }
+
+    true
+}
+
+// Checks if a class member is marked `private` or uses a `#private` name
+fn is_private_member(source_code: &str, node: &Node) -> bool {
+    let mut cursor = node.walk();
+    let is_private = node.named_children(&mut cursor).any(|child| {
+        child.kind() == "private_property_identifier"
+            || (child.kind() == "accessibility_modifier"
+                && child.utf8_text(source_code.as_bytes()).unwrap() == "private")
+    });
+    is_private
 }
 
 fn process_functions(
     source_code: &str,
     node: &Node,
-    updated_code: &mut String,
-    comment: &Option<String>,
+    documentation: &mut Documentation,
+    pending: &PendingComments,
+    options: &Options,
+    symbols: &SymbolTable,
 ) {
     let indentation = get_indentation(source_code, node);
     let mut js_doc = JsDoc::new(&indentation);
+    js_doc
+        .set_type_style(options.type_style)
+        .set_symbols(symbols, options.type_references);
 
     let info = get_function_details_from_node(source_code, node);
     // println!("info: {:?}", info);
 
-    match comment {
+    // An outdated block keeps its descriptions and the tags that aren't generated
+    let existing = pending
+        .doc_block
+        .as_ref()
+        .map(|doc| blocks::parse_block(source_code, doc.clone()));
+    // A block with only tags gets a description like a function without one
+    let description = match &existing {
+        Some(existing) => Some(existing.description.join("\n")).filter(|d| !d.is_empty()),
+        None => pending.description.clone(),
+    };
+    if let Some(existing) = existing {
+        js_doc.keep(existing);
+    }
+
+    match &description {
         Some(comment) => {
             // let comment = child.utf8_text(source_code.as_bytes())
             js_doc.add_description(comment);
         }
         None => {
-            js_doc.add_description(&info.function_name);
+            js_doc.add_description(&describe(source_code, node, &info, options));
         }
     }
 
     // println!("comment ... within function: {:?}", comment);
 
-    if node.kind() == "export_statement" {
-        let params = node.child(0).unwrap().next_named_sibling().unwrap();
-        get_params(source_code, &params, &mut js_doc);
+    let declaration = if node.kind() == "export_statement" {
+        node.child(0).unwrap().next_named_sibling().unwrap()
     } else {
-        get_params(source_code, node, &mut js_doc);
+        *node
+    };
+    let mut callbacks = Vec::new();
+    let param_comments = get_params(
+        source_code,
+        &declaration,
+        &info.function_name,
+        &mut js_doc,
+        options,
+        &mut callbacks,
+    );
+
+    if infer::is_generator(&declaration) {
+        let types = declaration
+            .child_by_field_name("return_type")
+            .and_then(|return_type| infer::annotated_generator_types(source_code, &return_type))
+            .unwrap_or_else(|| infer::infer_types(source_code, &declaration));
+
+        js_doc.add_generator();
+        if let Some(yield_type) = types.yields {
+            js_doc.add_yields(&yield_type, "");
+        }
+        if let Some(return_type) = types.returns {
+            js_doc.add_return(&return_type, "");
+        }
+    } else if let Some(return_type) = info.return_type {
+        js_doc.add_return(&return_type, "");
+    } else if options.infer_returns && info.function_name != "constructor" {
+        match infer::infer_types(source_code, &declaration).returns {
+            Some(return_type) if return_type == "void" && options.skip_void_returns => {}
+            Some(return_type) => {
+                js_doc.add_return(&return_type, "");
+            }
+            None => {}
+        }
     }
 
-    if let Some(return_type) = info.return_type {
-        js_doc.add_return(&return_type, "");
+    if options.infer_throws {
+        for error in infer::infer_throws(source_code, &declaration) {
+            js_doc.add_throws(&error, "");
+        }
     }
 
-    updated_code.push_str(&format!("{}\n", js_doc.build())); // add in the JsDoc
+    // An existing block with the tags that would be generated is left as it is written, along with
+    // its descriptions and any other tags
+    // but stripped param comments move into it, so then it is written again
+    let block = js_doc.build();
+    let strip = options.strip_param_comments && !param_comments.is_empty();
+    let up_to_date = !strip
+        && pending
+            .doc_block
+            .as_ref()
+            .is_some_and(|doc| blocks::is_up_to_date(source_code, doc, &block));
+
+    let mut edits = Vec::new();
+    if !up_to_date {
+        // remove the comments that moved into the JsDoc
+        pending.remove_description(&mut edits);
+
+        // add in any callback types used by the params, ahead of the JsDoc
+        let mut inserted = String::new();
+        for callback in callbacks {
+            inserted.push_str(&format!("{}\n{}", callback, indentation));
+        }
+        inserted.push_str(&format!("{}\n{}", block, indentation)); // add in the JsDoc
+        edits.push(Edit::insert(node.start_byte(), &inserted));
+    }
+
+    if strip {
+        edits.push(Edit::replace(
+            node.byte_range(),
+            &strip_comments(source_code, node, &param_comments),
+        ));
+    }
+
+    documentation.edits.extend(edits.iter().cloned());
+    documentation.functions.push(FunctionDoc {
+        name: info.function_name,
+        range: node.byte_range(),
+        existing_doc: pending.doc_block.clone(),
+        edits,
+        ..js_doc.function_doc().clone()
+    });
+}
 
-    // add the node
-    let node = node.utf8_text(source_code.as_bytes()).unwrap();
-    updated_code.push_str(&format!("{}{}", indentation, node));
+// Generate a description for a function without a comment
+fn describe(source_code: &str, node: &Node, info: &FunctionInfo, options: &Options) -> String {
+    match options.description {
+        DescriptionStyle::Name => info.function_name.clone(),
+        DescriptionStyle::Todo => "TODO".to_owned(),
+        DescriptionStyle::Humanize => {
+            if node.kind() == "method_definition" && info.function_name == "constructor" {
+                let class_name = node
+                    .parent()
+                    .and_then(|body| body.parent())
+                    .and_then(|class| class.child_by_field_name("name"))
+                    .map(|name| name.utf8_text(source_code.as_bytes()).unwrap());
+                humanize::describe_constructor(class_name)
+            } else {
+                humanize::describe_function(&info.function_name, &options.verb_rules)
+            }
+        }
+    }
 }
 
 fn get_function_details_from_node(source_code: &str, node: &Node) -> FunctionInfo {
     let mut cursor = node.walk();
     for child in node.children(&mut cursor) {
         // println!("aa: {:?}", return_type);
-        if child.kind() == "property_identifier" || child.kind() == "identifier" {
+        if child.kind() == "property_identifier"
+            || child.kind() == "private_property_identifier"
+            || child.kind() == "identifier"
+        {
             let return_type = get_function_return_type_from_node(source_code, node);
             let name = child
                 .utf8_text(source_code.as_bytes())
@@ -318,7 +968,7 @@ fn get_function_details_from_node(source_code: &str, node: &Node) -> FunctionInf
                 .trim()
                 .to_string();
             return FunctionInfo::new(name, return_type);
-        } else if child.kind() == "function_declaration" {
+        } else if is_function_declaration(&child) {
             let return_type = get_function_return_type_from_node(source_code, &child);
             let mut export_cursor = child.walk();
             for export_child in child.children(&mut export_cursor) {
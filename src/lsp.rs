@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, ErrorCode, ExtractError, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, Request as RequestTrait};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOptions, CodeActionOrCommand, CodeActionParams,
+    CodeActionProviderCapability, Diagnostic, DiagnosticSeverity, NumberOrString,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextEdit, Url, WorkspaceEdit,
+};
+
+use auto_js_doc::{Documentation, Edit, FunctionDoc, Options, Position, Session};
+
+const SOURCE_GENERATE: CodeActionKind = CodeActionKind::new("source.generateJsDoc");
+
+/// Run a language server over stdio until the client shuts it down, reporting the problems with the
+/// existing blocks too when asked to `validate` them
+pub fn run_lsp(options: Options, validate: bool) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![CodeActionKind::REFACTOR_REWRITE, SOURCE_GENERATE]),
+            ..CodeActionOptions::default()
+        })),
+        ..ServerCapabilities::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    let mut server = Server {
+        options,
+        validate,
+        documents: HashMap::new(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                connection
+                    .sender
+                    .send(Message::Response(server.handle_request(request)))?;
+            }
+            Message::Notification(notification) => {
+                if let Some(params) = server.handle_notification(notification) {
+                    connection
+                        .sender
+                        .send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_owned(),
+                            params,
+                        )))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    // The writer thread only finishes once the connection is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+// The open documents, kept in sync with the client and parsed incrementally as they change
+struct Server {
+    options: Options,
+    validate: bool,
+    documents: HashMap<Url, Session>,
+}
+
+impl Server {
+    // Answer a request, only code actions are supported
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        match request.extract::<CodeActionParams>(CodeActionRequest::METHOD) {
+            Ok((id, params)) => {
+                let uri = &params.text_document.uri;
+                let actions = match self.documents.get(uri) {
+                    Some(session) => code_actions(
+                        uri,
+                        session.source(),
+                        session.documentation(),
+                        &params.range.start,
+                        params.context.only.as_deref(),
+                    ),
+                    None => Vec::new(),
+                };
+                Response::new_ok(id, actions)
+            }
+            Err(ExtractError::MethodMismatch(request)) => Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request: {}", request.method),
+            ),
+            Err(ExtractError::JsonError { method, error }) => Response::new_err(
+                id,
+                ErrorCode::InvalidParams as i32,
+                format!("invalid params for {}: {}", method, error),
+            ),
+        }
+    }
+
+    // Keep the documents in sync, returning the diagnostics to publish for a changed document
+    fn handle_notification(
+        &mut self,
+        notification: Notification,
+    ) -> Option<PublishDiagnosticsParams> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification
+                    .extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+                    .ok()?;
+                let uri = params.text_document.uri;
+                let session = Session::new(self.options_for(&uri), &params.text_document.text);
+                self.documents.insert(uri.clone(), session);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification
+                    .extract::<lsp_types::DidChangeTextDocumentParams>(
+                        DidChangeTextDocument::METHOD,
+                    )
+                    .ok()?;
+                // Full sync, so the last change holds the whole text
+                let text = params.content_changes.into_iter().last()?.text;
+                let uri = params.text_document.uri;
+                self.documents.get_mut(&uri)?.set_source(&text);
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification
+                    .extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+                    .ok()?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                return Some(PublishDiagnosticsParams::new(uri, Vec::new(), None));
+            }
+            _ => return None,
+        };
+
+        let session = self.documents.get(&uri)?;
+        let mut found = session.documentation().diagnostics(session.source());
+        if self.validate {
+            found.extend(session.validate());
+        }
+        let diagnostics = lsp_diagnostics(session.source(), found);
+        Some(PublishDiagnosticsParams::new(uri, diagnostics, None))
+    }
+
+    // Point the options at the document so its relative imports can be followed
+    fn options_for(&self, uri: &Url) -> Options {
+        let mut options = self.options.clone();
+        options.source_path = uri.to_file_path().ok();
+        options
+    }
+}
+
+// The "Generate JSDoc" or "Update JSDoc" action for the function under the cursor and the whole file action
+fn code_actions(
+    uri: &Url,
+    source_code: &str,
+    documentation: &Documentation,
+    cursor: &lsp_types::Position,
+    only: Option<&[CodeActionKind]>,
+) -> Vec<CodeActionOrCommand> {
+    let cursor = Position {
+        line: cursor.line as usize,
+        column: cursor.character as usize,
+    }
+    .offset(source_code);
+    let wanted = |kind: &CodeActionKind| {
+        only.is_none_or(|only| {
+            only.iter()
+                .any(|prefix| kind.as_str().starts_with(prefix.as_str()))
+        })
+    };
+
+    let mut actions = Vec::new();
+
+    let function = documentation
+        .functions
+        .iter()
+        .filter(|function| {
+            let start = function
+                .existing_doc
+                .as_ref()
+                .map_or(function.range.start, |doc| doc.start);
+            (start..=function.range.end).contains(&cursor)
+        })
+        .min_by_key(|function| function.range.len());
+    if let Some(function) = function.filter(|_| wanted(&CodeActionKind::REFACTOR_REWRITE)) {
+        let title = match function.existing_doc {
+            None => Some("Generate JSDoc"),
+            Some(_) if function.is_outdated(source_code) => Some("Update JSDoc"),
+            Some(_) => None,
+        };
+        if let Some(title) = title {
+            actions.push(code_action(
+                title,
+                CodeActionKind::REFACTOR_REWRITE,
+                uri,
+                source_code,
+                &[function],
+            ));
+        }
+    }
+
+    let changed = documentation
+        .functions
+        .iter()
+        .filter(|function| function.existing_doc.is_none() || function.is_outdated(source_code))
+        .collect::<Vec<_>>();
+    if !changed.is_empty() && wanted(&SOURCE_GENERATE) {
+        actions.push(code_action(
+            "Generate JSDoc for the file",
+            SOURCE_GENERATE,
+            uri,
+            source_code,
+            &changed,
+        ));
+    }
+
+    actions
+}
+
+// A code action applying the edits for the functions
+fn code_action(
+    title: &str,
+    kind: CodeActionKind,
+    uri: &Url,
+    source_code: &str,
+    functions: &[&FunctionDoc],
+) -> CodeActionOrCommand {
+    // Clients don't allow the edits for a function to touch, so send one edit per function
+    let edits = functions
+        .iter()
+        .filter_map(|function| Edit::merge(source_code, &function.edits))
+        .map(|edit| TextEdit::new(to_range(source_code, &edit.range), edit.replacement))
+        .collect();
+
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.to_owned(),
+        kind: Some(kind),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        ..CodeAction::default()
+    })
+}
+
+// The missing and outdated JsDoc diagnostics, and any problems validating the existing blocks
+fn lsp_diagnostics(source_code: &str, found: Vec<auto_js_doc::Diagnostic>) -> Vec<Diagnostic> {
+    found
+        .into_iter()
+        .map(|diagnostic| Diagnostic {
+            range: to_range(source_code, &diagnostic.range),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(diagnostic.rule.to_owned())),
+            source: Some("auto-js-doc".to_owned()),
+            message: diagnostic.message_with_suggestion(),
+            ..Diagnostic::default()
+        })
+        .collect()
+}
+
+fn to_range(source_code: &str, range: &std::ops::Range<usize>) -> lsp_types::Range {
+    let position = |byte| {
+        let position = Position::of(source_code, byte);
+        lsp_types::Position::new(position.line as u32, position.column as u32)
+    };
+    lsp_types::Range::new(position(range.start), position(range.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use auto_js_doc::Documenter;
+
+    fn titles(actions: &[CodeActionOrCommand]) -> Vec<&str> {
+        actions
+            .iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title.as_str(),
+                CodeActionOrCommand::Command(command) => command.title.as_str(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_code_actions() {
+        let uri = Url::parse("file:///a.ts").unwrap();
+        let source_code = "function a() {}\n\n/**\n * b\n */\nfunction b() {}\n\n/**\n * old\n */\nfunction c(d: string) {}\n";
+        let documentation = Documenter::new().document(source_code);
+        let at = |line| lsp_types::Position::new(line, 3);
+
+        let actions = code_actions(&uri, source_code, &documentation, &at(0), None);
+        assert_eq!(
+            titles(&actions),
+            ["Generate JSDoc", "Generate JSDoc for the file"]
+        );
+
+        // Up to date
+        let actions = code_actions(&uri, source_code, &documentation, &at(3), None);
+        assert_eq!(titles(&actions), ["Generate JSDoc for the file"]);
+
+        let actions = code_actions(&uri, source_code, &documentation, &at(10), None);
+        assert_eq!(
+            titles(&actions),
+            ["Update JSDoc", "Generate JSDoc for the file"]
+        );
+
+        let only = [SOURCE_GENERATE];
+        let actions = code_actions(&uri, source_code, &documentation, &at(0), Some(&only));
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(titles(&actions), ["Generate JSDoc for the file"]);
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].new_text, "/**\n * a\n */\n");
+    }
+
+    #[test]
+    fn test_update_keeps_descriptions() {
+        let uri = Url::parse("file:///a.ts").unwrap();
+        let source_code = "/**\n * Adds.\n *\n * @param {number} b - The first\n * @example\n *   a(1);\n */\nfunction a(b: number) {}\n\n/**\n * Adds more.\n *\n * @param {number} d - The first\n * @example\n *   c(1, 2);\n */\nfunction c(d: number, e: number) {}\n";
+        let documentation = Documenter::new().document(source_code);
+        let at = |line| lsp_types::Position::new(line, 3);
+
+        // Documented with descriptions and an example, only the file action for `c`
+        let actions = code_actions(&uri, source_code, &documentation, &at(7), None);
+        assert_eq!(titles(&actions), ["Generate JSDoc for the file"]);
+
+        let actions = code_actions(&uri, source_code, &documentation, &at(16), None);
+        assert_eq!(
+            titles(&actions),
+            ["Update JSDoc", "Generate JSDoc for the file"]
+        );
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(
+            edits[0].new_text,
+            "/**\n * Adds more.\n *\n * @param {number} d - The first\n * @param {number} e - \n * @example\n *   c(1, 2);\n */\n"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let source_code = "/**\n * a\n */\nfunction a() {}\nclass B {\n  b(c: string) {\n  }\n}\n";
+        let diagnostics = lsp_diagnostics(source_code, Documenter::new().check(source_code));
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Missing JSDoc for `b`");
+        assert_eq!(
+            diagnostics[0].range,
+            lsp_types::Range::new(
+                lsp_types::Position::new(5, 2),
+                lsp_types::Position::new(5, 16)
+            )
+        );
+    }
+}
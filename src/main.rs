@@ -0,0 +1,409 @@
+mod cache;
+mod cli;
+mod files;
+mod lsp;
+mod serve;
+mod watch;
+
+use auto_js_doc::{
+    apply_edits, default_jobs, edits_to_json, format_coverage, format_report, parse_unified_diff,
+    Documenter, LocatedEdit, ReportFormat, Summary,
+};
+use cache::Cache;
+use cli::{Cli, Output};
+use files::{coverage_files, document_files, source_files};
+use lsp::run_lsp;
+use serve::run_server;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use watch::run_watch;
+
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    let subcommand =
+        args.next_if(|arg| arg == "lsp" || arg == "serve" || arg == "watch" || arg == "coverage");
+
+    let cli = match Cli::from_args(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if subcommand.as_deref() == Some("coverage") {
+        run_coverage(cli);
+    }
+
+    if let Some(subcommand) = subcommand {
+        let result = match subcommand.as_str() {
+            "lsp" => run_lsp(cli.options, cli.validate),
+            "watch" => watch_dir(&cli.paths).and_then(|dir| run_watch(dir, &cli.options)),
+            _ => run_server(&cli),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if (cli.check || cli.validate) && cli.format.is_coverage() && cli.format != ReportFormat::Text {
+        eprintln!(
+            "--format json and cobertura are for coverage, --check writes text, eslint or sarif"
+        );
+        std::process::exit(1);
+    }
+
+    let multiple_files = cli.git_diff || !cli.paths.is_empty();
+    let check = cli.check || cli.validate;
+    let mut found = false;
+
+    // Create a handle to stdin
+    let stdin = io::stdin();
+    let mut handle = stdin.lock();
+
+    // Create a string to hold the entire input
+    let mut input = String::new();
+
+    // Read the entire input into the string, unless the files to document were named
+    let read = match cli.paths.is_empty() {
+        true => handle.read_to_string(&mut input).map(|_| ()),
+        false => Ok(()),
+    };
+    let output = match read {
+        Ok(_) if multiple_files => {
+            let (output, any) = process_files(&input, &cli);
+            found = any;
+            output
+        }
+        Ok(_) if check => {
+            let (report, any) = check_source(&input, &cli);
+            found = any;
+            report
+        }
+        Ok(_) => process_source(&input, cli),
+        Err(_) => "".to_owned(),
+    };
+
+    // Create a handle to stdout
+    let stdout = io::stdout();
+    let mut handle_out = stdout.lock();
+
+    // Files are updated in place and a text report can be empty, so there's nothing to write
+    if !(multiple_files || check) || !output.is_empty() {
+        // Write the processed input to stdout
+        if let Err(e) = writeln!(handle_out, "{}", output) {
+            eprintln!("Error writing to stdout: {}", e);
+        }
+    }
+
+    io::stdout().flush().ok();
+
+    // Fail a check with findings, for CI
+    if found {
+        std::process::exit(1);
+    }
+}
+
+// The directory `watch` was given wherever it is among the flags, the current one by default
+fn watch_dir(paths: &[PathBuf]) -> Result<&Path, Box<dyn std::error::Error + Sync + Send>> {
+    match paths {
+        [] => Ok(Path::new(".")),
+        [dir] => Ok(dir),
+        _ => Err("watch takes one directory".into()),
+    }
+}
+
+// Report how much of the files named on the command line (or the current directory) is documented,
+// exiting with 1 when it is below the minimums
+fn run_coverage(mut cli: Cli) -> ! {
+    if !cli.format.is_coverage() {
+        eprintln!(
+            "--format eslint and sarif are for --check, coverage writes text, json or cobertura"
+        );
+        std::process::exit(1);
+    }
+    if cli.paths.is_empty() {
+        cli.paths.push(PathBuf::from("."));
+    }
+
+    let files = source_files(&cli.paths)
+        .into_iter()
+        .map(|path| {
+            let mut options = cli.options.clone();
+            options.source_path = Some(path.clone());
+            (path, options)
+        })
+        .collect();
+    let jobs = cli.jobs.unwrap_or_else(default_jobs);
+    let mut coverage = Vec::new();
+    for result in coverage_files(files, jobs) {
+        match result {
+            Ok(file) => coverage.push(file),
+            Err((path, e)) => eprintln!("Error reading {}: {}", path.display(), e),
+        }
+    }
+    println!("{}", format_coverage(cli.format, &coverage));
+
+    let summary = Summary::new(coverage.iter().flat_map(|file| &file.symbols));
+    let minimums = [
+        ("Coverage", summary.total, cli.min_coverage),
+        (
+            "Exported coverage",
+            summary.exported,
+            cli.min_exported_coverage,
+        ),
+    ];
+    let mut failed = false;
+    for (name, count, minimum) in minimums {
+        if let Some(minimum) = minimum.filter(|minimum| count.percent() < *minimum) {
+            eprintln!(
+                "{} {:.1}% is below the minimum of {}%",
+                name,
+                count.percent(),
+                minimum
+            );
+            failed = true;
+        }
+    }
+    std::process::exit(failed as i32);
+}
+
+// Document the source read from stdin, or just the function at the cursor
+fn process_source(input: &str, cli: Cli) -> String {
+    let documenter = Documenter::new().options(cli.options);
+    let edits = match cli.at {
+        Some(cursor) => match documenter.document_at(input, cursor.offset(input)) {
+            Some(function) => function.edits,
+            None => {
+                eprintln!("No function found at the given position");
+                std::process::exit(1);
+            }
+        },
+        None if !cli.line_ranges.is_empty() => {
+            documenter.document_lines(input, &cli.line_ranges).edits
+        }
+        None => documenter.edits(input),
+    };
+    match cli.output {
+        Output::Source => apply_edits(input, &edits),
+        Output::Edits => edits_to_json(input, &edits),
+    }
+}
+
+// Report the missing and outdated blocks of the source read from stdin and the problems with the
+// existing ones, along with whether there were any
+fn check_source(input: &str, cli: &Cli) -> (String, bool) {
+    let path = cli
+        .options
+        .source_path
+        .as_ref()
+        .map_or("<stdin>".to_owned(), |path| path.display().to_string());
+    let documenter = Documenter::new().options(cli.options.clone());
+    let mut diagnostics = match cli.check {
+        true if !cli.line_ranges.is_empty() => documenter
+            .document_lines(input, &cli.line_ranges)
+            .diagnostics(input),
+        true => documenter.check(input),
+        false => Vec::new(),
+    };
+    if cli.validate {
+        diagnostics.extend(documenter.validate(input));
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    }
+
+    let found = !diagnostics.is_empty();
+    (format_report(cli.format, &[(path, diagnostics)]), found)
+}
+
+// Document the files named on the command line, or the changed declarations of the files in a diff,
+// updating them in place, returning their edits by path or reporting on them with `--check` and
+// `--validate`, along with whether a check found anything
+fn process_files(diff: &str, cli: &Cli) -> (String, bool) {
+    let with_path = |path: &Path| {
+        let mut options = cli.options.clone();
+        options.source_path = Some(path.to_owned());
+        options
+    };
+    let files = if cli.git_diff {
+        parse_unified_diff(diff)
+            .into_iter()
+            .map(|(path, line_ranges)| (path.clone(), with_path(&path), line_ranges))
+            .collect()
+    } else {
+        source_files(&cli.paths)
+            .into_iter()
+            .map(|path| (path.clone(), with_path(&path), cli.line_ranges.clone()))
+            .collect()
+    };
+
+    // An up to date file stays that way unless it or the options change, but with imports followed
+    // other files matter too, and edits are printed for every file. The cache doesn't know whether
+    // the blocks of a file are valid, so `--validate` reads every file
+    let mut cache = cli
+        .cache_dir
+        .as_ref()
+        .filter(|_| !cli.options.follow_imports && cli.output == Output::Source && !cli.validate)
+        .map(|dir| Cache::open(dir, &cli.options));
+    // Only a file documented as a whole is known to be up to date afterwards
+    let whole_files = !cli.git_diff && cli.line_ranges.is_empty();
+
+    let jobs = cli.jobs.unwrap_or_else(default_jobs);
+    let mut documented = Vec::new();
+    for result in document_files(files, jobs, cache.as_ref(), cli.validate) {
+        match result {
+            Ok(file) => documented.push(file),
+            Err((path, e)) => eprintln!("Error reading {}: {}", path.display(), e),
+        }
+    }
+
+    if cli.check || cli.validate {
+        let reports = documented
+            .iter()
+            .map(|file| {
+                let mut diagnostics = match cli.check {
+                    true => file.documentation.diagnostics(&file.source_code),
+                    false => Vec::new(),
+                };
+                diagnostics.extend(file.validation.iter().cloned());
+                diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+                (file.path.display().to_string(), diagnostics)
+            })
+            .collect::<Vec<_>>();
+        let found = reports
+            .iter()
+            .any(|(_, diagnostics)| !diagnostics.is_empty());
+        if let Some(cache) = &mut cache {
+            for (file, (_, diagnostics)) in documented.iter().zip(&reports) {
+                // Without `--check` nothing says whether the file is missing blocks, and edits like
+                // `--strip-param-comments` aren't reported
+                let unchanged =
+                    apply_edits(&file.source_code, &file.documentation.edits) == file.source_code;
+                if cli.check && diagnostics.is_empty() && unchanged && whole_files {
+                    cache.insert(&file.path, &file.source_code);
+                }
+            }
+            save_cache(cache);
+        }
+        return (format_report(cli.format, &reports), found);
+    }
+
+    let mut edits_by_path = BTreeMap::new();
+    for file in documented {
+        let edits = &file.documentation.edits;
+        match cli.output {
+            // Up to date and already in the cache
+            Output::Source if file.cached => {}
+            Output::Source => {
+                // Edits can leave the file as it was, e.g. a block that is already up to date
+                let source_code = apply_edits(&file.source_code, edits);
+                let written = match source_code == file.source_code {
+                    true => Ok(()),
+                    false => fs::write(&file.path, &source_code),
+                };
+                match written {
+                    Ok(()) => {
+                        if let Some(cache) = cache.as_mut().filter(|_| whole_files) {
+                            cache.insert(&file.path, &source_code);
+                        }
+                    }
+                    Err(e) => eprintln!("Error writing {}: {}", file.path.display(), e),
+                }
+            }
+            Output::Edits => {
+                let located = edits
+                    .iter()
+                    .map(|edit| edit.locate(&file.source_code))
+                    .collect::<Vec<LocatedEdit>>();
+                edits_by_path.insert(file.path.display().to_string(), located);
+            }
+        }
+    }
+
+    if let Some(cache) = &cache {
+        save_cache(cache);
+    }
+
+    let output = match cli.output {
+        Output::Source => "".to_owned(),
+        Output::Edits => serde_json::to_string_pretty(&edits_by_path).unwrap(),
+    };
+    (output, false)
+}
+
+// Report failing to write the cache, which only costs documenting the files again next time
+fn save_cache(cache: &Cache) {
+    if let Err(e) = cache.save() {
+        eprintln!("Error writing the cache: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_dir() {
+        let cli = |args: &[&str]| Cli::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(watch_dir(&cli(&[]).paths).unwrap(), Path::new("."));
+        assert_eq!(
+            watch_dir(&cli(&["--exported-only", "src"]).paths).unwrap(),
+            Path::new("src")
+        );
+        assert!(watch_dir(&cli(&["src", "lib"]).paths).is_err());
+    }
+
+    #[test]
+    fn test_validate_then_document() {
+        let dir = std::env::temp_dir().join(format!("auto-js-doc-main-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.ts");
+        fs::write(&path, "export function a(b: string) {}\n").unwrap();
+        let cli = |validate| Cli {
+            validate,
+            cache_dir: Some(dir.join("cache")),
+            paths: vec![path.clone()],
+            ..Cli::default()
+        };
+
+        // Valid, but not documented
+        assert_eq!(process_files("", &cli(true)), ("".to_owned(), false));
+        process_files("", &cli(false));
+        let source_code = fs::read_to_string(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            source_code,
+            "/**\n * a\n *\n * @param {string} b - \n */\nexport function a(b: string) {}\n"
+        );
+    }
+
+    #[test]
+    fn test_check_then_document() {
+        let dir = std::env::temp_dir().join(format!("auto-js-doc-check-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.ts");
+        let documented =
+            "/**\n * a\n *\n * @param {string} b - \n */\nfunction a(\n  b: string, // The b\n) {}\n";
+        fs::write(&path, documented).unwrap();
+        let mut cli = Cli {
+            check: true,
+            cache_dir: Some(dir.join("cache")),
+            paths: vec![path.clone()],
+            ..Cli::default()
+        };
+        cli.options.strip_param_comments = true;
+
+        // Nothing missing or outdated, but the comment still moves into the block
+        assert_eq!(process_files("", &cli), ("".to_owned(), false));
+        cli.check = false;
+        process_files("", &cli);
+        let source_code = fs::read_to_string(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_ne!(source_code, documented);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::symbols::TypeReferences;
+use crate::types::TypeStyle;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// How to describe a function that has no existing comment
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DescriptionStyle {
+    /// Use the function name as it is
+    #[default]
+    Name,
+    /// Turn the function name into a sentence (`getUserById` -> "Gets the user by id.")
+    Humanize,
+    /// Leave a TODO placeholder
+    Todo,
+}
+
+/// How `--check` reports the diagnostics and `coverage` the coverage
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A `path:line:column` line per diagnostic, or a table of the coverage
+    #[default]
+    Text,
+    /// ESLint's JSON formatter output, for `--check`
+    Eslint,
+    /// A SARIF 2.1.0 log for code scanning, for `--check`
+    Sarif,
+    /// The coverage of each file and its declarations, for `coverage`
+    Json,
+    /// Cobertura's XML, for `coverage`
+    Cobertura,
+}
+
+impl ReportFormat {
+    /// Checks if `coverage` can write the format, the others are for `--check`
+    pub fn is_coverage(&self) -> bool {
+        matches!(
+            self,
+            ReportFormat::Text | ReportFormat::Json | ReportFormat::Cobertura
+        )
+    }
+}
+
+/// Controls which declarations get a JsDoc block generated and how it is written
+///
+/// Deserializes from camelCase JSON (`{ "exportedOnly": true, "typeStyle": "closure" }`), leaving out
+/// the settings reading from disk. New settings can be added in any release, so start from
+/// `Options::default()` and set the fields you need.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct Options {
+    /// Only document exported functions and members of exported classes
+    pub exported_only: bool,
+    /// Only document public and protected class members
+    pub public_only: bool,
+    /// Skip class constructors
+    pub skip_constructors: bool,
+    /// Skip functions spanning fewer than this many lines
+    pub min_lines: Option<usize>,
+    /// Skip functions whose name matches this pattern (e.g. `^_`)
+    #[serde(deserialize_with = "deserialize_pattern")]
+    pub ignore_pattern: Option<Regex>,
+    /// How to describe functions without a comment
+    pub description: DescriptionStyle,
+    /// Extra verb rules for the humanizer as `(verb, template)`, `{}` is replaced with the rest of the name
+    pub verb_rules: Vec<(String, String)>,
+    /// Remove parameter comments from the signature once they are moved into the JsDoc
+    pub strip_param_comments: bool,
+    /// Infer `@returns` and `@yields` from the body when there is no return type annotation
+    pub infer_returns: bool,
+    /// Leave out `@returns {void}` when inferring return types
+    pub skip_void_returns: bool,
+    /// Add `@throws` for errors thrown or rejected with in the body
+    pub infer_throws: bool,
+    /// Describe function typed params with a named `@callback` block instead of inline
+    pub callback_typedefs: bool,
+    /// How types are written, either as TypeScript or translated for JSDoc tooling
+    pub type_style: TypeStyle,
+    /// How named types are written, kept, inlined, linked with `@see` or as `import()` types
+    pub type_references: TypeReferences,
+    /// The path of the file being processed, used to find relative imports
+    #[serde(skip)]
+    pub source_path: Option<PathBuf>,
+    /// Read relative imports from disk so the aliases they export can be inlined
+    #[serde(skip)]
+    pub follow_imports: bool,
+    /// Only document declarations touching these one based line ranges, all of them when empty,
+    /// set with [`Documenter::document_lines`](crate::Documenter::document_lines)
+    #[serde(skip)]
+    pub(crate) line_ranges: Vec<RangeInclusive<usize>>,
+}
+
+// Compile the ignore pattern when reading options from JSON
+fn deserialize_pattern<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Regex>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|pattern| Regex::new(&pattern).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let options: Options = serde_json::from_str(
+            r#"{
+                "exportedOnly": true,
+                "minLines": 2,
+                "ignorePattern": "^_",
+                "description": "humanize",
+                "verbRules": [["fetch", "Retrieves the {}."]],
+                "typeStyle": "jsdoc-strict",
+                "typeReferences": "see"
+            }"#,
+        )
+        .unwrap();
+
+        assert!(options.exported_only);
+        assert_eq!(options.min_lines, Some(2));
+        assert!(options.ignore_pattern.unwrap().is_match("_a"));
+        assert_eq!(options.description, DescriptionStyle::Humanize);
+        assert_eq!(options.verb_rules.len(), 1);
+        assert_eq!(options.type_style, TypeStyle::JsdocStrict);
+        assert_eq!(options.type_references, TypeReferences::See);
+
+        assert!(serde_json::from_str::<Options>(r#"{ "ignorePattern": "(" }"#).is_err());
+        assert!(serde_json::from_str::<Options>(r#"{ "typeStyle": "nope" }"#).is_err());
+    }
+}
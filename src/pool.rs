@@ -0,0 +1,54 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// The number of threads used when none is given, one per core
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |jobs| jobs.get())
+}
+
+/// Read each file and pass it to `f` along with its settings on a pool of `jobs` threads
+///
+/// The results are in the order the files were given, whichever thread finished first.
+pub fn map_files<S: Sync, T: Send>(
+    files: Vec<(PathBuf, S)>,
+    jobs: usize,
+    f: impl Fn(&Path, String, &S) -> T + Sync,
+) -> Vec<Result<T, (PathBuf, io::Error)>> {
+    let next = AtomicUsize::new(0);
+    let files = &files;
+    let next = &next;
+    let f = &f;
+
+    let mut results = thread::scope(|scope| {
+        let workers = (0..jobs.clamp(1, files.len().max(1)))
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut results = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((path, settings)) = files.get(index) else {
+                            break;
+                        };
+                        let result = match fs::read_to_string(path) {
+                            Ok(source_code) => Ok(f(path, source_code, settings)),
+                            Err(e) => Err((path.clone(), e)),
+                        };
+                        results.push((index, result));
+                    }
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,272 @@
+use serde::Serialize;
+use serde_json::json;
+
+use crate::diagnostics::{
+    Diagnostic, MISSING_JSDOC, MISSING_PARAM, OPTIONAL_MISMATCH, OUTDATED_JSDOC, PARAM_ORDER,
+    TYPE_MISMATCH, UNKNOWN_PARAM, VOID_RETURNS,
+};
+use crate::options::ReportFormat;
+
+// The rules with a short description, as listed in SARIF reports
+const RULES: [(&str, &str); 8] = [
+    (MISSING_JSDOC, "Functions should have a JsDoc block"),
+    (
+        OUTDATED_JSDOC,
+        "JsDoc blocks should match the function signature",
+    ),
+    (UNKNOWN_PARAM, "`@param` tags should name a parameter"),
+    (MISSING_PARAM, "Every parameter should have a `@param` tag"),
+    (
+        PARAM_ORDER,
+        "`@param` tags should be in the order of the parameters",
+    ),
+    (
+        TYPE_MISMATCH,
+        "`@param` and `@returns` types should match the annotations",
+    ),
+    (
+        VOID_RETURNS,
+        "Functions that return nothing shouldn't have a `@returns` tag",
+    ),
+    (
+        OPTIONAL_MISMATCH,
+        "`@param` tags should agree with the parameter on being optional and its default",
+    ),
+];
+
+// A file in ESLint's JSON formatter output
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EslintResult<'a> {
+    file_path: &'a str,
+    messages: Vec<EslintMessage>,
+    suppressed_messages: [(); 0],
+    error_count: usize,
+    fatal_error_count: usize,
+    warning_count: usize,
+    fixable_error_count: usize,
+    fixable_warning_count: usize,
+    used_deprecated_rules: [(); 0],
+}
+
+// Lines and columns are one based, with columns in UTF-16 code units
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EslintMessage {
+    rule_id: &'static str,
+    // 1 for a warning, 2 for an error
+    severity: u8,
+    message: String,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+/// Write the diagnostics of each file (by path) in the format, `Json` and `Cobertura` are coverage
+/// formats and write text
+pub fn format_report(format: ReportFormat, files: &[(String, Vec<Diagnostic>)]) -> String {
+    match format {
+        ReportFormat::Text | ReportFormat::Json | ReportFormat::Cobertura => text(files),
+        ReportFormat::Eslint => eslint(files),
+        ReportFormat::Sarif => sarif(files),
+    }
+}
+
+// One `path:line:column` line per diagnostic, like compilers do, with any suggestion under it
+fn text(files: &[(String, Vec<Diagnostic>)]) -> String {
+    files
+        .iter()
+        .flat_map(|(path, diagnostics)| {
+            diagnostics.iter().map(move |diagnostic| {
+                let line = format!(
+                    "{}:{}:{}: warning: {} ({})",
+                    path,
+                    diagnostic.start.line + 1,
+                    diagnostic.start.column + 1,
+                    diagnostic.message,
+                    diagnostic.rule
+                );
+                match &diagnostic.suggestion {
+                    Some(suggestion) => format!("{}\n  help: {}", line, suggestion),
+                    None => line,
+                }
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn eslint(files: &[(String, Vec<Diagnostic>)]) -> String {
+    let results = files
+        .iter()
+        .map(|(path, diagnostics)| EslintResult {
+            file_path: path,
+            messages: diagnostics
+                .iter()
+                .map(|diagnostic| EslintMessage {
+                    rule_id: diagnostic.rule,
+                    severity: 1,
+                    message: diagnostic.message_with_suggestion(),
+                    line: diagnostic.start.line + 1,
+                    column: diagnostic.start.column + 1,
+                    end_line: diagnostic.end.line + 1,
+                    end_column: diagnostic.end.column + 1,
+                })
+                .collect(),
+            suppressed_messages: [],
+            error_count: 0,
+            fatal_error_count: 0,
+            warning_count: diagnostics.len(),
+            fixable_error_count: 0,
+            fixable_warning_count: 0,
+            used_deprecated_rules: [],
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&results).unwrap()
+}
+
+// A SARIF 2.1.0 log with a single run, columns default to UTF-16 code units there too
+fn sarif(files: &[(String, Vec<Diagnostic>)]) -> String {
+    let rules = RULES
+        .iter()
+        .map(|(id, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": "warning" }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = files
+        .iter()
+        .flat_map(|(path, diagnostics)| {
+            diagnostics.iter().map(move |diagnostic| {
+                json!({
+                    "ruleId": diagnostic.rule,
+                    "ruleIndex": RULES.iter().position(|(id, _)| *id == diagnostic.rule),
+                    "level": "warning",
+                    "message": { "text": diagnostic.message_with_suggestion() },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": path },
+                            "region": {
+                                "startLine": diagnostic.start.line + 1,
+                                "startColumn": diagnostic.start.column + 1,
+                                "endLine": diagnostic.end.line + 1,
+                                "endColumn": diagnostic.end.column + 1
+                            }
+                        }
+                    }]
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "auto-js-doc",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules
+                }
+            },
+            "results": results
+        }]
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Documenter, Options};
+
+    fn files() -> Vec<(String, Vec<Diagnostic>)> {
+        let source_code = "function a() {}\n/**\n * b\n */\nfunction b(c: string) {}\n";
+        let diagnostics = Documenter::new()
+            .options(Options::default())
+            .check(source_code);
+        vec![
+            ("src/a.ts".to_owned(), diagnostics),
+            ("src/b.ts".to_owned(), Vec::new()),
+        ]
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            format_report(ReportFormat::Text, &files()),
+            "src/a.ts:1:1: warning: Missing JSDoc for `a` (auto-js-doc/missing-jsdoc)\nsrc/a.ts:2:1: warning: Outdated JSDoc for `b` (auto-js-doc/outdated-jsdoc)"
+        );
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let source_code = "/**\n * @param {string} old\n */\nfunction a(new: string) {}\n";
+        let files = vec![(
+            "src/a.ts".to_owned(),
+            Documenter::new().validate(source_code),
+        )];
+        assert_eq!(
+            format_report(ReportFormat::Text, &files),
+            "src/a.ts:2:4: warning: `@param old` isn't a parameter of `a` (auto-js-doc/unknown-param)\n  help: Rename it to `new`"
+        );
+
+        let report = format_report(ReportFormat::Eslint, &files);
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(
+            value[0]["messages"][0]["message"],
+            "`@param old` isn't a parameter of `a`. Rename it to `new`"
+        );
+
+        let report = format_report(ReportFormat::Sarif, &files);
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(value["runs"][0]["results"][0]["ruleIndex"], 2);
+    }
+
+    #[test]
+    fn test_eslint() {
+        let report = format_report(ReportFormat::Eslint, &files());
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+
+        assert_eq!(value.as_array().unwrap().len(), 2);
+        assert_eq!(value[0]["filePath"], "src/a.ts");
+        assert_eq!(value[0]["warningCount"], 2);
+        assert_eq!(value[1]["warningCount"], 0);
+        assert_eq!(
+            value[0]["messages"][1],
+            json!({
+                "ruleId": "auto-js-doc/outdated-jsdoc",
+                "severity": 1,
+                "message": "Outdated JSDoc for `b`",
+                "line": 2,
+                "column": 1,
+                "endLine": 4,
+                "endColumn": 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_sarif() {
+        let report = format_report(ReportFormat::Sarif, &files());
+        let value: serde_json::Value = serde_json::from_str(&report).unwrap();
+
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], OUTDATED_JSDOC);
+        assert_eq!(run["results"].as_array().unwrap().len(), 2);
+        assert_eq!(run["results"][1]["ruleIndex"], 1);
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "src/a.ts" },
+                "region": { "startLine": 1, "startColumn": 1, "endLine": 1, "endColumn": 16 }
+            })
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::Diagnostic;
+use crate::documenter::Documenter;
+use crate::edits::{apply_edits, LocatedEdit};
+use crate::options::Options;
+
+/// The options `POST /process` and the WebAssembly `process` take, camelCase like [`Options`]
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct ProcessOptions {
+    #[serde(flatten)]
+    pub options: Options,
+    /// Also report the problems with the existing blocks in the diagnostics
+    pub validate: bool,
+}
+
+/// The documented source, with the edits and diagnostics for the source as given
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessResult {
+    pub source: String,
+    pub edits: Vec<LocatedEdit>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Document a source for the server or the WebAssembly bindings
+pub fn process_source(source_code: &str, options: ProcessOptions) -> ProcessResult {
+    let documenter = Documenter::new().options(options.options);
+    let documentation = documenter.document(source_code);
+    let mut diagnostics = documentation.diagnostics(source_code);
+    if options.validate {
+        diagnostics.extend(documenter.validate(source_code));
+    }
+
+    ProcessResult {
+        source: apply_edits(source_code, &documentation.edits),
+        edits: documentation
+            .edits
+            .iter()
+            .map(|edit| edit.locate(source_code))
+            .collect(),
+        diagnostics,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The JSON the server and `serde_wasm_bindgen` read the options from and write the result to
+    #[test]
+    fn test_process_source() {
+        let options: ProcessOptions = serde_json::from_str(
+            r#"{ "description": "todo", "typeStyle": "closure", "validate": true }"#,
+        )
+        .unwrap();
+        let source = "/** @param {string} b */\nfunction a(c: string) {}\nfunction d() {}";
+        let result = serde_json::to_value(process_source(source, options)).unwrap();
+
+        assert_eq!(
+            result["source"],
+            "/**\n * TODO\n *\n * @param {string} c - \n */\nfunction a(c: string) {}\n/**\n * TODO\n */\nfunction d() {}"
+        );
+        let diagnostics = result["diagnostics"].as_array().unwrap();
+        assert_eq!(
+            result["edits"][2]["start"],
+            serde_json::json!({ "line": 2, "column": 0 })
+        );
+        assert_eq!(diagnostics[0]["rule"], "auto-js-doc/outdated-jsdoc");
+        assert_eq!(diagnostics[1]["rule"], "auto-js-doc/missing-jsdoc");
+        assert_eq!(diagnostics[1]["name"], "d");
+        assert_eq!(
+            diagnostics[1]["start"],
+            serde_json::json!({ "line": 2, "column": 0 })
+        );
+        assert_eq!(diagnostics[2]["rule"], "auto-js-doc/unknown-param");
+        assert_eq!(diagnostics[2]["suggestion"], "Rename it to `c`");
+        assert!(diagnostics[0].get("suggestion").is_none());
+
+        let options: ProcessOptions = serde_json::from_str("{}").unwrap();
+        assert!(!options.validate);
+        assert!(serde_json::from_str::<ProcessOptions>(r#"{ "typeStyle": "nope" }"#).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use auto_js_doc::{process_source, ProcessOptions};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::cli::Cli;
+
+/// The address listened on when none is given
+pub const DEFAULT_BIND: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+/// The port served on when none is given
+pub const DEFAULT_PORT: u16 = 3000;
+/// The largest request body accepted when no limit is given, 1MB
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// The demo page
+const INDEX: &str = include_str!("../site/index.html");
+// Where `wasm-pack` puts the bindings for the page to run the documenter itself
+const DEFAULT_SITE_DIR: &str = "site";
+
+// The body of `POST /process`
+#[derive(Debug, Deserialize)]
+struct ProcessRequest {
+    source: String,
+    #[serde(default)]
+    options: ProcessOptions,
+}
+
+// What to send back, as the status, content type and body
+#[derive(Debug, PartialEq, Eq)]
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: Serialize>(status: u16, value: &T) -> Reply {
+        Reply {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(value).unwrap(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Reply {
+        Reply::json(status, &serde_json::json!({ "error": message }))
+    }
+}
+
+/// Serve the demo page on `/` and the documenter on `POST /process`
+pub fn run_server(cli: &Cli) -> Result<(), Box<dyn Error + Sync + Send>> {
+    let bind = cli.bind.unwrap_or(DEFAULT_BIND);
+    // Anyone on the network could reach it, so the hosts it is deployed on have to be named
+    if !bind.is_loopback() && cli.allowed_hosts.is_empty() {
+        return Err(format!("listening on {} needs --allowed-host", bind).into());
+    }
+    let port = cli.port.unwrap_or(DEFAULT_PORT);
+    let server = Server::http((bind, port))?;
+    eprintln!("Listening on http://{}:{}", bind, port);
+
+    for mut request in server.incoming_requests() {
+        let host = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Host"))
+            .map(|header| header.value.as_str().to_owned());
+        let method = request.method().clone();
+        let url = request.url().to_owned();
+        let body_length = request.body_length();
+        let reply = respond(
+            cli,
+            &method,
+            &url,
+            host.as_deref(),
+            body_length,
+            request.as_reader(),
+        );
+
+        let content_type = Header::from_bytes("Content-Type", reply.content_type).unwrap();
+        let response = Response::from_data(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Error responding: {}", e);
+        }
+    }
+    Ok(())
+}
+
+// Route a request
+fn respond(
+    cli: &Cli,
+    method: &Method,
+    url: &str,
+    host: Option<&str>,
+    body_length: Option<usize>,
+    body: &mut dyn Read,
+) -> Reply {
+    // Only answer for the hosts we are deployed on, or localhost when none are given
+    let allowed = match cli.allowed_hosts.is_empty() {
+        true => host.is_some_and(is_localhost),
+        false => host.is_some_and(|host| cli.allowed_hosts.iter().any(|allowed| allowed == host)),
+    };
+    if !allowed {
+        return Reply::error(403, "Host not allowed");
+    }
+
+    let path = url.split('?').next().unwrap_or(url);
+    match (method, path) {
+        (Method::Get, "/") => Reply {
+            status: 200,
+            content_type: "text/html",
+            body: INDEX.into(),
+        },
+        (Method::Get, _) if path.starts_with("/pkg/") => {
+            let site_dir = cli
+                .site_dir
+                .as_deref()
+                .unwrap_or(Path::new(DEFAULT_SITE_DIR));
+            static_file(site_dir, path.trim_start_matches('/'))
+        }
+        (Method::Post, "/process") => {
+            let max_body_size = cli.max_body_size.unwrap_or(DEFAULT_MAX_BODY_SIZE);
+            if body_length.is_some_and(|length| length > max_body_size) {
+                return Reply::error(413, "Request body too large");
+            }
+
+            // The length header can be left out, so stop reading past the limit as well
+            let mut text = String::new();
+            if body
+                .take(max_body_size as u64 + 1)
+                .read_to_string(&mut text)
+                .is_err()
+            {
+                return Reply::error(400, "Request body is not valid UTF-8");
+            }
+            if text.len() > max_body_size {
+                return Reply::error(413, "Request body too large");
+            }
+
+            match serde_json::from_str::<ProcessRequest>(&text) {
+                Ok(request) => Reply::json(200, &process_source(&request.source, request.options)),
+                Err(e) => Reply::error(400, &format!("Invalid request: {}", e)),
+            }
+        }
+        (_, "/" | "/process") => Reply::error(405, "Method not allowed"),
+        _ => Reply::error(404, "Not found"),
+    }
+}
+
+// Checks if a `Host` header names this machine, with or without a port
+fn is_localhost(host: &str) -> bool {
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name,
+        _ => host,
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+// Serve a file built by `wasm-pack`, keeping to the directory
+fn static_file(site_dir: &Path, path: &str) -> Reply {
+    let content_type = match path.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("js") => "text/javascript",
+        Some("wasm") => "application/wasm",
+        Some("ts") => "text/plain",
+        _ => return Reply::error(404, "Not found"),
+    };
+    if path
+        .split('/')
+        .any(|part| part.is_empty() || part.starts_with('.'))
+    {
+        return Reply::error(404, "Not found");
+    }
+
+    match fs::read(site_dir.join(path)) {
+        Ok(body) => Reply {
+            status: 200,
+            content_type,
+            body,
+        },
+        Err(_) => Reply::error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(cli: &Cli, host: &str, body: &str) -> Reply {
+        respond(
+            cli,
+            &Method::Post,
+            "/process",
+            Some(host),
+            Some(body.len()),
+            &mut body.as_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_process() {
+        let cli = Cli::default();
+        let reply = post(
+            &cli,
+            "localhost:3000",
+            r#"{ "source": "function a() {}", "options": { "description": "todo" } }"#,
+        );
+        assert_eq!(reply.status, 200);
+
+        let body: serde_json::Value = serde_json::from_slice(&reply.body).unwrap();
+        assert_eq!(body["source"], "/**\n * TODO\n */\nfunction a() {}");
+        assert_eq!(body["edits"].as_array().unwrap().len(), 1);
+        assert_eq!(body["diagnostics"][0]["rule"], "auto-js-doc/missing-jsdoc");
+        assert!(body["diagnostics"][0].get("suggestion").is_none());
+
+        let reply = post(
+            &cli,
+            "localhost:3000",
+            r#"{ "source": "/** @param {string} b */\nfunction a(c: string) {}", "options": { "validate": true } }"#,
+        );
+        let body: serde_json::Value = serde_json::from_slice(&reply.body).unwrap();
+        assert_eq!(body["diagnostics"][1]["rule"], "auto-js-doc/unknown-param");
+        assert_eq!(body["diagnostics"][1]["suggestion"], "Rename it to `c`");
+
+        let reply = post(&cli, "localhost:3000", r#"{ "options": {} }"#);
+        assert_eq!(reply.status, 400);
+    }
+
+    #[test]
+    fn test_limits() {
+        let cli = Cli {
+            allowed_hosts: vec!["auto-js-doc.fly.dev".to_owned()],
+            max_body_size: Some(32),
+            ..Cli::default()
+        };
+
+        let reply = post(&cli, "evil.example", r#"{ "source": "" }"#);
+        assert_eq!(reply.status, 403);
+
+        let reply = post(&cli, "auto-js-doc.fly.dev", r#"{ "source": "" }"#);
+        assert_eq!(reply.status, 200);
+
+        let body = format!(r#"{{ "source": "{}" }}"#, "a".repeat(64));
+        let reply = post(&cli, "auto-js-doc.fly.dev", &body);
+        assert_eq!(reply.status, 413);
+
+        // Without a length header
+        let reply = respond(
+            &cli,
+            &Method::Post,
+            "/process",
+            Some("auto-js-doc.fly.dev"),
+            None,
+            &mut body.as_bytes(),
+        );
+        assert_eq!(reply.status, 413);
+    }
+
+    #[test]
+    fn test_localhost_only() {
+        let cli = Cli::default();
+        let body = r#"{ "source": "" }"#;
+
+        assert_eq!(post(&cli, "localhost", body).status, 200);
+        assert_eq!(post(&cli, "127.0.0.1:3000", body).status, 200);
+        assert_eq!(post(&cli, "[::1]:3000", body).status, 200);
+        assert_eq!(post(&cli, "evil.example", body).status, 403);
+        assert_eq!(post(&cli, "localhost.evil.example", body).status, 403);
+
+        // Without a `Host` header
+        let reply = respond(
+            &cli,
+            &Method::Post,
+            "/process",
+            None,
+            None,
+            &mut body.as_bytes(),
+        );
+        assert_eq!(reply.status, 403);
+
+        let cli = Cli {
+            bind: Some(IpAddr::from([0, 0, 0, 0])),
+            ..Cli::default()
+        };
+        assert!(run_server(&cli).is_err());
+    }
+
+    #[test]
+    fn test_routes() {
+        let cli = Cli::default();
+        let get = |url| {
+            respond(
+                &cli,
+                &Method::Get,
+                url,
+                Some("localhost:3000"),
+                None,
+                &mut "".as_bytes(),
+            )
+        };
+
+        assert_eq!(get("/").content_type, "text/html");
+        assert_eq!(get("/process").status, 405);
+        assert_eq!(get("/nope").status, 404);
+        assert_eq!(get("/pkg/../Cargo.toml").status, 404);
+        assert_eq!(get("/pkg/../src/lib.js").status, 404);
+    }
+
+    #[test]
+    fn test_static_files() {
+        let site_dir =
+            std::env::temp_dir().join(format!("auto-js-doc-site-{}", std::process::id()));
+        fs::create_dir_all(site_dir.join("pkg")).unwrap();
+        fs::write(site_dir.join("pkg/auto_js_doc.js"), "export {}").unwrap();
+
+        let cli = Cli {
+            site_dir: Some(site_dir.clone()),
+            ..Cli::default()
+        };
+        let get = |url| {
+            respond(
+                &cli,
+                &Method::Get,
+                url,
+                Some("localhost:3000"),
+                None,
+                &mut "".as_bytes(),
+            )
+        };
+
+        let reply = get("/pkg/auto_js_doc.js");
+        let missing = get("/pkg/missing.wasm");
+        fs::remove_dir_all(&site_dir).unwrap();
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.content_type, "text/javascript");
+        assert_eq!(reply.body, b"export {}");
+        assert_eq!(missing.status, 404);
+    }
+}
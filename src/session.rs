@@ -0,0 +1,460 @@
+use std::ops::{Range, RangeInclusive};
+use tree_sitter::{InputEdit, Node, Parser, Point, Tree};
+
+use crate::diagnostics::Diagnostic;
+use crate::documenter::Documentation;
+use crate::options::Options;
+use crate::structs::FunctionDoc;
+use crate::symbols::TypeReferences;
+use crate::validate::validate_tree;
+
+/// A source kept parsed between edits, for editors and watchers documenting the same file over and over
+///
+/// Each edit reparses incrementally from the previous tree and only documents the declarations it
+/// touched again, keeping the rest of the documentation as it was.
+///
+/// ```
+/// use auto_js_doc::{Options, Session};
+///
+/// let mut session = Session::new(Options::default(), "function a() {}\n");
+/// session.edit(16..16, "function b() {}\n");
+/// assert_eq!(session.documentation().functions.len(), 2);
+/// ```
+pub struct Session {
+    parser: Parser,
+    options: Options,
+    source_code: String,
+    tree: Tree,
+    documentation: Documentation,
+}
+
+impl Session {
+    pub fn new(options: Options, source_code: &str) -> Session {
+        let mut parser = crate::parser();
+        let tree = parser.parse(source_code, None).unwrap();
+        let documentation = crate::document_tree(source_code, &tree, &options);
+        Session {
+            parser,
+            options,
+            source_code: source_code.to_owned(),
+            tree,
+            documentation,
+        }
+    }
+
+    /// The source as of the last edit
+    pub fn source(&self) -> &str {
+        &self.source_code
+    }
+
+    /// The documentation of the source as of the last edit
+    pub fn documentation(&self) -> &Documentation {
+        &self.documentation
+    }
+
+    /// The problems with the existing JsDoc blocks of the source as of the last edit
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        validate_tree(&self.source_code, &self.tree, &self.documentation)
+    }
+
+    /// Replace the whole source, for clients that send full documents, treating what differs as one edit
+    pub fn set_source(&mut self, source_code: &str) -> &Documentation {
+        let old = self.source_code.as_bytes();
+        let new = source_code.as_bytes();
+        let mut prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        while !source_code.is_char_boundary(prefix) || !self.source_code.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let mut suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !source_code.is_char_boundary(new.len() - suffix)
+            || !self.source_code.is_char_boundary(old.len() - suffix)
+        {
+            suffix -= 1;
+        }
+
+        self.edit(
+            prefix..old.len() - suffix,
+            &source_code[prefix..new.len() - suffix],
+        )
+    }
+
+    /// Replace the text in the byte range, reparsing and documenting the declarations it touched again
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> &Documentation {
+        let start = range.start;
+        let old_end = range.end;
+        let new_end = start + text.len();
+
+        // Checked before the old text is gone, as removing a directive or a type changes other declarations
+        let removes_shared = affects_others(&self.source_code, &self.tree.root_node(), &range);
+        let start_position = point(&self.source_code, start);
+        let old_end_position = point(&self.source_code, old_end);
+        self.source_code.replace_range(range, text);
+
+        let mut old_tree = self.tree.clone();
+        old_tree.edit(&InputEdit {
+            start_byte: start,
+            old_end_byte: old_end,
+            new_end_byte: new_end,
+            start_position,
+            old_end_position,
+            new_end_position: point(&self.source_code, new_end),
+        });
+        self.tree = self
+            .parser
+            .parse(&self.source_code, Some(&old_tree))
+            .unwrap();
+
+        let mut changed = old_tree
+            .changed_ranges(&self.tree)
+            .map(|range| range.start_byte..range.end_byte)
+            .collect::<Vec<_>>();
+        changed.push(start..new_end);
+
+        // Named types are looked up across the file, so anything can depend on them, whether a declaration
+        // is in the line ranges in the options depends on more than what changed, and error recovery can
+        // change how code far from the edit is read without tree-sitter reporting it
+        if removes_shared
+            || changed
+                .iter()
+                .any(|range| affects_others(&self.source_code, &self.tree.root_node(), range))
+            || self.options.type_references != TypeReferences::Keep
+            || !self.options.line_ranges.is_empty()
+            || old_tree.root_node().has_error()
+            || self.tree.root_node().has_error()
+        {
+            self.documentation = crate::document_tree(&self.source_code, &self.tree, &self.options);
+            return &self.documentation;
+        }
+
+        // Tree-sitter doesn't report everything under a node that now parses as something else
+        let touched = changed
+            .iter()
+            .map(|range| whole_lines(&self.source_code, range))
+            .collect::<Vec<_>>();
+        restructured(
+            &old_tree.root_node(),
+            &self.tree.root_node(),
+            &touched,
+            &mut changed,
+        );
+
+        // Move the functions after the edit along, the ones it touched get documented again
+        let shift = |offset: usize| {
+            if offset <= start {
+                offset
+            } else if offset >= old_end {
+                offset + new_end - old_end
+            } else {
+                new_end
+            }
+        };
+        let mut kept = Vec::new();
+        for function in std::mem::take(&mut self.documentation.functions) {
+            let span = span(&function);
+            if span.end < start || span.start > old_end {
+                kept.push(shift_function(function, shift));
+            } else {
+                changed.push(shift(span.start)..shift(span.end));
+            }
+        }
+
+        let lines = changed
+            .iter()
+            .map(|range| line_numbers(&self.source_code, range))
+            .collect::<Vec<_>>();
+        // The changed lines as bytes, to find the functions documented again
+        let line_starts = line_starts(&self.source_code);
+        let changed = lines
+            .iter()
+            .map(|lines| {
+                let end = line_starts
+                    .get(*lines.end())
+                    .map_or(self.source_code.len(), |next| next - 1);
+                line_starts[lines.start() - 1]..end
+            })
+            .collect::<Vec<_>>();
+
+        let documented = crate::document_tree(
+            &self.source_code,
+            &self.tree,
+            &Options {
+                line_ranges: lines,
+                ..self.options.clone()
+            },
+        );
+
+        kept.retain(|function| {
+            let span = span(function);
+            !changed
+                .iter()
+                .any(|range| range.start <= span.end && span.start <= range.end)
+                && !documented
+                    .functions
+                    .iter()
+                    .any(|documented| documented.range == function.range)
+        });
+        kept.extend(documented.functions);
+        kept.sort_by_key(|function| function.range.start);
+
+        self.documentation = Documentation {
+            edits: kept
+                .iter()
+                .flat_map(|function| function.edits.iter().cloned())
+                .collect(),
+            functions: kept,
+        };
+        &self.documentation
+    }
+}
+
+// Checks the lines of the range for directives and the types that params are described from
+fn affects_others(source_code: &str, root_node: &Node, range: &Range<usize>) -> bool {
+    let lines = whole_lines(source_code, range);
+    if source_code[lines.clone()].contains("auto-js-doc") {
+        return true;
+    }
+
+    let mut cursor = root_node.walk();
+    let affects_others = root_node
+        .children(&mut cursor)
+        .filter(|child| child.start_byte() <= lines.end && lines.start <= child.end_byte())
+        .any(|child| {
+            let declaration = match child.kind() {
+                "export_statement" => child.child_by_field_name("declaration"),
+                _ => Some(child),
+            };
+            declaration.is_some_and(|declaration| {
+                matches!(
+                    declaration.kind(),
+                    "interface_declaration" | "type_alias_declaration"
+                )
+            })
+        });
+    affects_others
+}
+
+// Add the ranges of the declarations touched by the changes that were read differently before
+fn restructured(old: &Node, new: &Node, changed: &[Range<usize>], ranges: &mut Vec<Range<usize>>) {
+    let old_children = touched_children(old, changed);
+    let new_children = touched_children(new, changed);
+
+    let same = old_children.len() == new_children.len()
+        && old_children
+            .iter()
+            .zip(&new_children)
+            .all(|(old, new)| old.kind() == new.kind() && old.byte_range() == new.byte_range());
+    if !same {
+        ranges.extend(
+            old_children
+                .iter()
+                .chain(&new_children)
+                .map(|child| child.byte_range()),
+        );
+        return;
+    }
+
+    for (old, new) in old_children.iter().zip(&new_children) {
+        if let (Some(old), Some(new)) = (class_body(old), class_body(new)) {
+            restructured(&old, &new, changed, ranges);
+        }
+    }
+}
+
+fn touched_children<'a>(node: &Node<'a>, changed: &[Range<usize>]) -> Vec<Node<'a>> {
+    let mut cursor = node.walk();
+    let touched = node
+        .children(&mut cursor)
+        .filter(|child| {
+            changed
+                .iter()
+                .any(|range| child.start_byte() <= range.end && range.start <= child.end_byte())
+        })
+        .collect::<Vec<_>>();
+    touched
+}
+
+fn class_body<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    let class = match node.kind() {
+        "export_statement" => node.child_by_field_name("declaration")?,
+        _ => *node,
+    };
+    (class.kind() == "class_declaration")
+        .then(|| class.child_by_field_name("body"))
+        .flatten()
+}
+
+// The bytes of the source from the start of the declaration's comments to its end
+fn span(function: &FunctionDoc) -> Range<usize> {
+    let ranges = function
+        .edits
+        .iter()
+        .map(|edit| &edit.range)
+        .chain(&function.existing_doc)
+        .chain([&function.range]);
+    let start = ranges.clone().map(|range| range.start).min().unwrap();
+    let end = ranges.map(|range| range.end).max().unwrap();
+    start..end
+}
+
+fn shift_function(mut function: FunctionDoc, shift: impl Fn(usize) -> usize) -> FunctionDoc {
+    let shift_range = |range: &mut Range<usize>| *range = shift(range.start)..shift(range.end);
+    shift_range(&mut function.range);
+    if let Some(existing_doc) = &mut function.existing_doc {
+        shift_range(existing_doc);
+    }
+    for edit in &mut function.edits {
+        shift_range(&mut edit.range);
+    }
+    function
+}
+
+// Widen the range to the start and end of its lines
+fn whole_lines(source_code: &str, range: &Range<usize>) -> Range<usize> {
+    let start = source_code[..range.start].rfind('\n').map_or(0, |n| n + 1);
+    let end = source_code[range.end..]
+        .find('\n')
+        .map_or(source_code.len(), |n| range.end + n);
+    start..end
+}
+
+// The one based lines of the range
+fn line_numbers(source_code: &str, range: &Range<usize>) -> RangeInclusive<usize> {
+    let start = source_code[..range.start].matches('\n').count() + 1;
+    let end = start + source_code[range.clone()].matches('\n').count();
+    start..=end
+}
+
+// The byte offset each line starts at
+fn line_starts(source_code: &str) -> Vec<usize> {
+    let newlines = source_code.match_indices('\n').map(|(n, _)| n + 1);
+    [0].into_iter().chain(newlines).collect()
+}
+
+// Tree-sitter counts columns in bytes
+fn point(source_code: &str, byte: usize) -> Point {
+    let before = &source_code[..byte];
+    let line_start = before.rfind('\n').map_or(0, |n| n + 1);
+    Point::new(before.matches('\n').count(), byte - line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+// Adds two numbers
+function add(a: number, b: number): number {
+    return a + b;
+}
+
+/**
+ * Old
+ */
+export function subtract(a: number) {}
+
+class Calculator {
+    // Multiplies
+    multiply(a: number, b: number) {
+        return a * b;
+    }
+
+    divide(a: number) {}
+}
+"#;
+
+    // Apply the edits to the session and check it matches documenting from scratch each time
+    fn check(options: Options, edits: &[(&str, &str)]) {
+        let mut session = Session::new(options.clone(), SOURCE);
+        for (find, replacement) in edits {
+            let start = session.source().find(find).unwrap();
+            let documentation = session.edit(start..start + find.len(), replacement).clone();
+            assert_eq!(
+                documentation,
+                crate::document(session.source(), &options),
+                "after replacing {:?} with {:?}",
+                find,
+                replacement
+            );
+        }
+    }
+
+    #[test]
+    fn test_edit() {
+        check(
+            Options::default(),
+            &[
+                // In a body
+                ("a + b", "a + b + 1"),
+                // A parameter
+                (
+                    "a: number, b: number)",
+                    "a: number, b: string, c?: boolean)",
+                ),
+                // A description
+                ("Adds two numbers", "Adds numbers"),
+                // A new function between a comment and the function it described
+                ("function add", "function first() {}\nfunction add"),
+                // Removed again
+                ("function first() {}\n", ""),
+                // A method and a class member
+                ("multiply(a", "times(a"),
+                (
+                    "divide(a: number) {}",
+                    "divide(a: number) {}\n    modulo() {}",
+                ),
+                // A whole declaration
+                ("export function subtract(a: number) {}", ""),
+                // Indentation
+                ("    modulo", "  modulo"),
+                // Directives and types
+                (
+                    "class Calculator",
+                    "// auto-js-doc-ignore\nclass Calculator",
+                ),
+                ("// auto-js-doc-ignore\n", ""),
+                ("b: string", "b: Item"),
+                (
+                    "\nclass",
+                    "\ninterface Item { /** The id */ id: string }\nclass",
+                ),
+                ("// Multiplies", "/* auto-js-doc-disable */"),
+                ("Calculator", "Calculatör"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_edit_with_options() {
+        check(
+            Options {
+                exported_only: true,
+                line_ranges: vec![1..=12],
+                ..Options::default()
+            },
+            &[
+                ("function add", "export function add"),
+                ("class Calculator", "export class Calculator"),
+                ("multiply(a", "times(a"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_set_source() {
+        let mut session = Session::new(Options::default(), "function a() {}\n");
+        session.set_source("function é() {}\nfunction b(c: string) {}\n");
+        assert_eq!(
+            session.documentation(),
+            &crate::document(session.source(), &Options::default())
+        );
+        assert_eq!(session.documentation().functions.len(), 2);
+
+        session.set_source("");
+        assert!(session.documentation().functions.is_empty());
+    }
+}
@@ -1,36 +1,216 @@
+use crate::blocks::is_up_to_date;
+use crate::blocks::DocBlock;
+use crate::edits::Edit;
+use crate::symbols::{SymbolTable, TypeReferences};
+use crate::types::{format_type, TypeStyle};
+use std::ops::Range;
+
+/// What was extracted from a documented function's signature
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FunctionDoc {
+    pub name: String,
+    /// The byte range of the declaration in the source
+    pub range: Range<usize>,
+    pub description: String,
+    pub params: Vec<ParamDoc>,
+    /// The return type as written or inferred
+    pub returns: Option<String>,
+    pub generator: bool,
+    pub yields: Option<String>,
+    pub throws: Vec<String>,
+    /// The JsDoc block already in front of the function, if any
+    pub existing_doc: Option<Range<usize>>,
+    /// The edits documenting this function
+    pub edits: Vec<Edit>,
+}
+
+impl FunctionDoc {
+    /// Checks whether the existing JsDoc block has different `@param` or `@returns` tags than the
+    /// one that would be generated, ignoring descriptions and the tags that aren't generated
+    pub fn is_outdated(&self, source_code: &str) -> bool {
+        let Some(doc) = &self.existing_doc else {
+            return false;
+        };
+        // The edit inserting the generated block in front of the function
+        self.edits
+            .iter()
+            .filter(|edit| edit.range.is_empty() && edit.range.start == self.range.start)
+            .any(|edit| !is_up_to_date(source_code, doc, &edit.replacement))
+    }
+}
+
+/// A parameter of a documented function, properties of object params are named `param.property`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParamDoc {
+    pub name: String,
+    /// The type as written in the signature
+    pub param_type: Option<String>,
+    pub optional: bool,
+    pub default: Option<String>,
+    pub description: String,
+}
+
 #[derive(Debug)]
-pub struct JsDoc {
+pub struct JsDoc<'a> {
     indentation: String,
     formatted: String,
-    param_count: u8,
+    tags_started: bool,
+    type_style: TypeStyle,
+    symbols: Option<&'a SymbolTable>,
+    type_references: TypeReferences,
+    see: Vec<String>,
+    // The existing block being replaced, for the descriptions and tags to keep
+    existing: Option<DocBlock>,
+    function_doc: FunctionDoc,
 }
 
-impl JsDoc {
-    pub fn new(indentation: &str) -> JsDoc {
+impl<'a> JsDoc<'a> {
+    pub fn new(indentation: &str) -> JsDoc<'a> {
         JsDoc {
             indentation: indentation.to_owned(),
             formatted: "/**\n".to_string(),
-            param_count: 0,
+            tags_started: false,
+            type_style: TypeStyle::default(),
+            symbols: None,
+            type_references: TypeReferences::default(),
+            see: Vec::new(),
+            existing: None,
+            function_doc: FunctionDoc::default(),
         }
     }
 
+    // Start another block with the same indentation and type settings
+    pub fn new_like(&self) -> JsDoc<'a> {
+        let mut js_doc = JsDoc::new(&self.indentation);
+        js_doc.type_style = self.type_style;
+        js_doc.symbols = self.symbols;
+        js_doc.type_references = self.type_references;
+        js_doc
+    }
+
+    pub fn set_type_style(&mut self, type_style: TypeStyle) -> &mut JsDoc<'a> {
+        self.type_style = type_style;
+        self
+    }
+
+    // Resolve named types against the symbols of the file
+    pub fn set_symbols(
+        &mut self,
+        symbols: &'a SymbolTable,
+        type_references: TypeReferences,
+    ) -> &mut JsDoc<'a> {
+        self.symbols = Some(symbols);
+        self.type_references = type_references;
+        self
+    }
+
+    // Keep the descriptions of the existing block for the params and return value that are still
+    // there, and its tags that aren't generated
+    pub fn keep(&mut self, existing: DocBlock) -> &mut JsDoc<'a> {
+        self.existing = Some(existing);
+        self
+    }
+
+    // The signature data added so far
+    pub fn function_doc(&self) -> &FunctionDoc {
+        &self.function_doc
+    }
+
     pub fn build(&mut self) -> String {
+        let other_tags = self
+            .existing
+            .take()
+            .map(|existing| existing.other_tags)
+            .unwrap_or_default();
+        for lines in other_tags {
+            // The generated `@throws` and `@see` tags replace the existing ones
+            let generated = match lines[0].split_whitespace().next() {
+                Some("@throws" | "@exception") => !self.function_doc.throws.is_empty(),
+                Some("@see") => !self.see.is_empty(),
+                _ => false,
+            };
+            if generated {
+                continue;
+            }
+            self.start_tags();
+            for line in lines {
+                self.add_line(&line);
+            }
+        }
+        for see in std::mem::take(&mut self.see) {
+            self.start_tags();
+            self.formatted
+                .push_str(&format!("{} * @see {}\n", self.indentation, see));
+        }
         self.formatted.push_str(&format!("{} */", self.indentation));
         self.formatted.clone()
     }
 
-    pub fn add_description(&mut self, description: &str) -> &mut JsDoc {
-        self.formatted
-            .push_str(&format!("{} * {}\n", self.indentation, description));
+    // Write a type in the configured style
+    fn format_type(&mut self, type_text: &str) -> String {
+        let type_text = match self.symbols {
+            Some(symbols) => symbols.resolve(type_text, self.type_references, &mut self.see),
+            None => type_text.to_owned(),
+        };
+        format_type(&type_text, self.type_style)
+    }
+
+    // Write a line of the block, without trailing whitespace when it is empty
+    fn add_line(&mut self, line: &str) {
+        match line.is_empty() {
+            true => self
+                .formatted
+                .push_str(&format!("{} *\n", self.indentation)),
+            false => self
+                .formatted
+                .push_str(&format!("{} * {}\n", self.indentation, line)),
+        }
+    }
+
+    // The rest of a description after its first line, which goes on the line of its tag
+    fn add_continued(&mut self, description: &str) {
+        for line in description.lines().skip(1) {
+            self.add_line(line);
+        }
+    }
+
+    pub fn add_description(&mut self, description: &str) -> &mut JsDoc<'a> {
+        self.function_doc.description = description.to_owned();
+        let mut lines = description.lines();
+        self.formatted.push_str(&format!(
+            "{} * {}\n",
+            self.indentation,
+            lines.next().unwrap_or("")
+        ));
+        for line in lines {
+            self.add_line(line);
+        }
         self
     }
 
-    pub fn add_space(&mut self) -> &mut JsDoc {
+    pub fn add_space(&mut self) -> &mut JsDoc<'a> {
         self.formatted
             .push_str(&format!("{} *\n", self.indentation));
         self
     }
 
+    // Separate the tags from the description, once
+    pub fn start_tags(&mut self) -> &mut JsDoc<'a> {
+        if !self.tags_started {
+            self.add_space();
+            self.tags_started = true;
+        }
+        self
+    }
+
+    // Method to name the block as a callback type
+    pub fn add_callback(&mut self, name: &str) -> &mut JsDoc<'a> {
+        self.tags_started = true;
+        self.formatted
+            .push_str(&format!("{} * @callback {}\n", self.indentation, name));
+        self
+    }
+
     // Method to add a parameter to the JsDoc
     pub fn add_param(
         &mut self,
@@ -39,30 +219,95 @@ impl JsDoc {
         optional: bool,
         default: Option<String>,
         description: &str,
-    ) -> &mut JsDoc {
-        self.param_count += 1;
+    ) -> &mut JsDoc<'a> {
+        self.tags_started = true;
+        let kept = self
+            .existing
+            .iter()
+            .flat_map(|existing| &existing.params)
+            .find(|existing| existing.name == param)
+            .map(|existing| existing.description.clone());
+        let description = match kept {
+            Some(kept) if description.is_empty() => kept,
+            _ => description.to_owned(),
+        };
+        let description = description.as_str();
+        self.function_doc.params.push(ParamDoc {
+            name: param.to_owned(),
+            param_type: param_type.clone(),
+            optional,
+            default: default.clone(),
+            description: description.to_owned(),
+        });
         let open_bracket = if optional { "[" } else { "" };
         let close_bracket = if optional { "]" } else { "" };
         let a = default
             .map(|val| format!("{open_bracket}{param}=\"{val}\"{close_bracket}"))
             .unwrap_or(format!("{open_bracket}{param}{close_bracket}"));
-        let param_type = param_type.unwrap_or_else(|| "unknown".to_owned());
+        let param_type = self.format_type(param_type.as_deref().unwrap_or("unknown"));
         self.formatted.push_str(&format!(
             "{} * @param {{{}}} {} - {}\n",
-            self.indentation, param_type, a, description
+            self.indentation,
+            param_type,
+            a,
+            description.lines().next().unwrap_or("")
+        ));
+        self.add_continued(description);
+        self
+    }
+
+    // Method to mark the function as a generator
+    pub fn add_generator(&mut self) -> &mut JsDoc<'a> {
+        self.start_tags();
+        self.function_doc.generator = true;
+        self.formatted
+            .push_str(&format!("{} * @generator\n", self.indentation));
+        self
+    }
+
+    // Method to add a yield type to the JsDoc
+    pub fn add_yields(&mut self, yield_type: &str, description: &str) -> &mut JsDoc<'a> {
+        self.start_tags();
+        self.function_doc.yields = Some(yield_type.to_owned());
+        let yield_type = self.format_type(yield_type);
+        self.formatted.push_str(&format!(
+            "{} * @yields {{{}}} {}\n",
+            self.indentation, yield_type, description
+        ));
+        self
+    }
+
+    // Method to add an error that can be thrown to the JsDoc
+    pub fn add_throws(&mut self, error_type: &str, description: &str) -> &mut JsDoc<'a> {
+        self.start_tags();
+        self.function_doc.throws.push(error_type.to_owned());
+        let error_type = self.format_type(error_type);
+        self.formatted.push_str(&format!(
+            "{} * @throws {{{}}} {}\n",
+            self.indentation, error_type, description
         ));
         self
     }
 
     // Method to add a return type to the JsDoc
-    pub fn add_return(&mut self, return_type: &str, description: &str) -> &mut JsDoc {
-        if self.param_count == 0 {
-            self.add_space();
-        }
+    pub fn add_return(&mut self, return_type: &str, description: &str) -> &mut JsDoc<'a> {
+        self.start_tags();
+        self.function_doc.returns = Some(return_type.to_owned());
+        let kept = self
+            .existing
+            .as_ref()
+            .and_then(|existing| existing.returns.as_ref())
+            .map(|returns| returns.description.clone())
+            .filter(|_| description.is_empty());
+        let description = kept.as_deref().unwrap_or(description);
+        let return_type = self.format_type(return_type);
         self.formatted.push_str(&format!(
             "{} * @returns {{{}}} {}\n",
-            self.indentation, return_type, description
+            self.indentation,
+            return_type,
+            description.lines().next().unwrap_or("")
         ));
+        self.add_continued(description);
         self
     }
 }
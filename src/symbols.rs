@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use tree_sitter::{Node, Parser};
+use tree_sitter_typescript::language_typescript;
+
+use crate::types::rewrite_references;
+
+/// How references to named types are written into the JsDoc
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypeReferences {
+    /// Write the name as it is
+    #[default]
+    Keep,
+    /// Replace type aliases with the type they stand for
+    Inline,
+    /// Keep the name and add a `@see` for it
+    See,
+    /// Write imported types as `import("./user").User`
+    Import,
+}
+
+/// A named type declared or imported in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symbol {
+    /// `type Id = string`
+    Alias { value: String },
+    /// An alias exported by an imported file, with the aliases of that file already inlined in its value
+    ImportedAlias { value: String },
+    /// An interface, class or enum
+    Declared,
+    /// `import { User } from "./user"`, `name` is the name it is exported under
+    Imported { module: String, name: String },
+    /// `import * as user from "./user"`
+    Namespace { module: String },
+}
+
+/// The named types known in a file
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolTable {
+    /// Collect the top level declarations and imports of a file
+    pub fn from_tree(source_code: &str, root_node: &Node) -> SymbolTable {
+        let mut symbols = HashMap::new();
+        let text = |node: &Node| node.utf8_text(source_code.as_bytes()).unwrap().to_owned();
+
+        let mut cursor = root_node.walk();
+        for child in root_node.named_children(&mut cursor) {
+            let declaration = if child.kind() == "export_statement" {
+                child.child_by_field_name("declaration").unwrap_or(child)
+            } else {
+                child
+            };
+            let name = declaration.child_by_field_name("name").map(|n| text(&n));
+
+            match (declaration.kind(), name) {
+                ("type_alias_declaration", Some(name)) => {
+                    // Generic aliases can't be inlined without substituting their parameters
+                    let symbol = match declaration.child_by_field_name("type_parameters") {
+                        Some(_) => Symbol::Declared,
+                        None => Symbol::Alias {
+                            value: declaration
+                                .child_by_field_name("value")
+                                .map(|v| text(&v))
+                                .unwrap_or_default(),
+                        },
+                    };
+                    symbols.insert(name, symbol);
+                }
+                (
+                    "interface_declaration"
+                    | "class_declaration"
+                    | "abstract_class_declaration"
+                    | "enum_declaration",
+                    Some(name),
+                ) => {
+                    symbols.insert(name, Symbol::Declared);
+                }
+                ("import_statement", _) => {
+                    collect_imports(source_code, &declaration, &mut symbols);
+                }
+                _ => {}
+            }
+        }
+
+        SymbolTable { symbols }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+
+    /// Read the relative imports from disk, so aliases exported by them can be inlined
+    pub fn load_imports(&mut self, source_path: &Path) {
+        let base_dir = source_path.parent().unwrap_or(Path::new("."));
+        let mut parser = Parser::new();
+        parser
+            .set_language(&language_typescript())
+            .expect("Error loading Typescript grammar");
+
+        let mut files: HashMap<String, Option<SymbolTable>> = HashMap::new();
+        for symbol in self.symbols.values_mut() {
+            let Symbol::Imported { module, name } = symbol else {
+                continue;
+            };
+            if !module.starts_with("./") && !module.starts_with("../") {
+                continue;
+            }
+
+            let table = files.entry(module.clone()).or_insert_with(|| {
+                let source_code = module_candidates(module)
+                    .iter()
+                    .find_map(|candidate| fs::read_to_string(base_dir.join(candidate)).ok())?;
+                let tree = parser.parse(&source_code, None)?;
+                Some(SymbolTable::from_tree(&source_code, &tree.root_node()))
+            });
+
+            // The names in its value are that file's, not this one's
+            if let Some((table, Symbol::Alias { value })) = table
+                .as_ref()
+                .and_then(|table| Some((table, table.get(name)?)))
+            {
+                *symbol = Symbol::ImportedAlias {
+                    value: table.inline(value, 1),
+                };
+            }
+        }
+    }
+
+    /// Rewrite the references in a type for the given mode, collecting the names to add `@see` for
+    pub fn resolve(&self, type_text: &str, mode: TypeReferences, see: &mut Vec<String>) -> String {
+        match mode {
+            TypeReferences::Keep => type_text.to_owned(),
+            TypeReferences::Inline => self.inline(type_text, 0),
+            TypeReferences::See => {
+                rewrite_references(type_text, |name, _| {
+                    let root = name.split('.').next().unwrap_or(name);
+                    let link = match self.get(root) {
+                        Some(Symbol::Imported { module, .. } | Symbol::Namespace { module }) => {
+                            format!("{{@link {}}} from \"{}\"", name, module)
+                        }
+                        Some(_) => format!("{{@link {}}}", name),
+                        None => return None,
+                    };
+                    if !see.contains(&link) {
+                        see.push(link);
+                    }
+                    None
+                });
+                type_text.to_owned()
+            }
+            TypeReferences::Import => {
+                rewrite_references(type_text, |name, _| match name.split_once('.') {
+                    Some((namespace, rest)) => match self.get(namespace) {
+                        Some(Symbol::Namespace { module }) => {
+                            Some(format!("import(\"{}\").{}", module, rest))
+                        }
+                        _ => None,
+                    },
+                    None => match self.get(name) {
+                        Some(Symbol::Imported { module, name }) => {
+                            Some(format!("import(\"{}\").{}", module, name))
+                        }
+                        _ => None,
+                    },
+                })
+            }
+        }
+    }
+
+    // Replace aliases with their values, following aliases of aliases a few levels deep
+    fn inline(&self, type_text: &str, depth: usize) -> String {
+        if depth > 8 {
+            return type_text.to_owned();
+        }
+        rewrite_references(type_text, |name, whole| {
+            let value = match self.get(name) {
+                Some(Symbol::Alias { value }) => self.inline(value, depth + 1),
+                Some(Symbol::ImportedAlias { value }) => value.clone(),
+                _ => return None,
+            };
+            // Keep precedence when the alias is part of a bigger type, `Status[]` -> `("a" | "b")[]`
+            let needs_parens = ["|", "&", "=>", "?"]
+                .iter()
+                .any(|operator| value.contains(operator));
+            if needs_parens && !whole {
+                Some(format!("({})", value))
+            } else {
+                Some(value)
+            }
+        })
+    }
+}
+
+// Add the names brought in by an import statement
+fn collect_imports(source_code: &str, import: &Node, symbols: &mut HashMap<String, Symbol>) {
+    let text = |node: &Node| node.utf8_text(source_code.as_bytes()).unwrap().to_owned();
+    let Some(module) = import
+        .child_by_field_name("source")
+        .map(|s| text(&s).trim_matches(['"', '\'']).to_owned())
+    else {
+        return;
+    };
+
+    let mut cursor = import.walk();
+    let Some(clause) = import
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "import_clause")
+    else {
+        return;
+    };
+
+    let mut clause_cursor = clause.walk();
+    for part in clause.named_children(&mut clause_cursor) {
+        match part.kind() {
+            // `import User from "./user"`
+            "identifier" => {
+                symbols.insert(
+                    text(&part),
+                    Symbol::Imported {
+                        module: module.clone(),
+                        name: "default".to_owned(),
+                    },
+                );
+            }
+            "namespace_import" => {
+                if let Some(name) = part.named_child(0) {
+                    symbols.insert(
+                        text(&name),
+                        Symbol::Namespace {
+                            module: module.clone(),
+                        },
+                    );
+                }
+            }
+            "named_imports" => {
+                let mut specifier_cursor = part.walk();
+                for specifier in part.named_children(&mut specifier_cursor) {
+                    let Some(name) = specifier.child_by_field_name("name") else {
+                        continue;
+                    };
+                    let local = specifier.child_by_field_name("alias").unwrap_or(name);
+                    symbols.insert(
+                        text(&local),
+                        Symbol::Imported {
+                            module: module.clone(),
+                            name: text(&name),
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// The files an import could point at, `./user.js` names the `./user.ts` it is compiled from
+fn module_candidates(module: &str) -> Vec<String> {
+    let compiled = [
+        (".js", ".ts"),
+        (".js", ".tsx"),
+        (".js", ".d.ts"),
+        (".jsx", ".tsx"),
+        (".mjs", ".mts"),
+        (".cjs", ".cts"),
+    ]
+    .iter()
+    .filter_map(|(js, ts)| Some(format!("{}{}", module.strip_suffix(js)?, ts)));
+    let extensions = ["", ".ts", ".tsx", ".d.ts", "/index.ts", "/index.tsx"]
+        .iter()
+        .map(|extension| format!("{}{}", module, extension));
+    compiled.chain(extensions).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(source_code: &str) -> SymbolTable {
+        let mut parser = Parser::new();
+        parser.set_language(&language_typescript()).unwrap();
+        let tree = parser.parse(source_code, None).unwrap();
+        SymbolTable::from_tree(source_code, &tree.root_node())
+    }
+
+    #[test]
+    fn test_from_tree() {
+        let symbols = table(
+            r#"
+            import { User, Other as O } from "./user";
+            import Def from "../def";
+            import * as m from "./m";
+            export type Id = string;
+            type Box<T> = T[];
+            interface I {}
+            "#,
+        );
+
+        assert_eq!(
+            symbols.get("O"),
+            Some(&Symbol::Imported {
+                module: "./user".to_owned(),
+                name: "Other".to_owned()
+            })
+        );
+        assert_eq!(
+            symbols.get("Def"),
+            Some(&Symbol::Imported {
+                module: "../def".to_owned(),
+                name: "default".to_owned()
+            })
+        );
+        assert_eq!(
+            symbols.get("m"),
+            Some(&Symbol::Namespace {
+                module: "./m".to_owned()
+            })
+        );
+        assert_eq!(
+            symbols.get("Id"),
+            Some(&Symbol::Alias {
+                value: "string".to_owned()
+            })
+        );
+        assert_eq!(symbols.get("Box"), Some(&Symbol::Declared));
+        assert_eq!(symbols.get("I"), Some(&Symbol::Declared));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let symbols = table(
+            r#"
+            import { User } from "./user";
+            import * as m from "./m";
+            type Id = string;
+            type Status = "on" | "off";
+            type Ids = Id[];
+            "#,
+        );
+        let mut see = Vec::new();
+
+        assert_eq!(
+            symbols.resolve("Ids", TypeReferences::Inline, &mut see),
+            "string[]"
+        );
+        assert_eq!(
+            symbols.resolve("Status[]", TypeReferences::Inline, &mut see),
+            "(\"on\" | \"off\")[]"
+        );
+        assert_eq!(
+            symbols.resolve("Map<Id, User>", TypeReferences::Import, &mut see),
+            "Map<Id, import(\"./user\").User>"
+        );
+        assert_eq!(
+            symbols.resolve("m.Thing", TypeReferences::Import, &mut see),
+            "import(\"./m\").Thing"
+        );
+        assert!(see.is_empty());
+
+        assert_eq!(
+            symbols.resolve("Id | User", TypeReferences::See, &mut see),
+            "Id | User"
+        );
+        assert_eq!(see, ["{@link Id}", "{@link User} from \"./user\""]);
+    }
+
+    #[test]
+    fn test_module_candidates() {
+        assert_eq!(module_candidates("./user.js")[0], "./user.ts");
+        assert_eq!(module_candidates("./user.mjs")[0], "./user.mts");
+        assert_eq!(module_candidates("./user")[0], "./user");
+    }
+}
@@ -0,0 +1,434 @@
+use serde::Deserialize;
+use tree_sitter::{Node, Parser, Tree};
+use tree_sitter_typescript::language_typescript;
+
+/// How types are written into the JsDoc
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypeStyle {
+    /// Keep the TypeScript syntax, with whitespace normalized
+    #[default]
+    Typescript,
+    /// Translate into Closure Compiler types (`Array<string>`, `?string`, `*`)
+    Closure,
+    /// Translate into types the JSDoc tooling understands, `unknown` becomes `*` rather than `?`
+    JsdocStrict,
+}
+
+/// Format a type for the JsDoc in the given style, falling back to `*` for anything that can't be translated
+pub fn format_type(type_text: &str, style: TypeStyle) -> String {
+    match style {
+        TypeStyle::Typescript => normalize(type_text),
+        _ => translate(type_text, style).unwrap_or_else(|| "*".to_owned()),
+    }
+}
+
+/// Rewrite the named types referenced in a type, `rewrite` gets each name and whether it is the whole type
+pub fn rewrite_references<F>(type_text: &str, mut rewrite: F) -> String
+where
+    F: FnMut(&str, bool) -> Option<String>,
+{
+    let source_code = format!("type __T = {};", type_text);
+    let Some(tree) = parse_type(&source_code) else {
+        return type_text.to_owned();
+    };
+    let Some(value) = tree
+        .root_node()
+        .named_child(0)
+        .and_then(|alias| alias.child_by_field_name("value"))
+    else {
+        return type_text.to_owned();
+    };
+
+    let mut rewritten = String::new();
+    let mut last_byte = value.start_byte();
+    let mut stack = vec![value];
+    let mut references = Vec::new();
+    while let Some(node) = stack.pop() {
+        if node.kind() == "type_identifier" || node.kind() == "nested_type_identifier" {
+            references.push(node);
+            continue;
+        }
+        let mut cursor = node.walk();
+        stack.extend(node.named_children(&mut cursor));
+    }
+    references.sort_by_key(|node| node.start_byte());
+
+    for reference in references {
+        let name = reference.utf8_text(source_code.as_bytes()).unwrap();
+        if let Some(replacement) = rewrite(name, reference.id() == value.id()) {
+            rewritten.push_str(&source_code[last_byte..reference.start_byte()]);
+            rewritten.push_str(&replacement);
+            last_byte = reference.end_byte();
+        }
+    }
+    rewritten.push_str(&source_code[last_byte..value.end_byte()]);
+    rewritten
+}
+
+// Parse a `type __T = ...;` wrapper, None if the type doesn't parse cleanly
+fn parse_type(source_code: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&language_typescript())
+        .expect("Error loading Typescript grammar");
+    let tree = parser.parse(source_code, None)?;
+    if tree.root_node().has_error() {
+        return None;
+    }
+    Some(tree)
+}
+
+// Parse a type as the return type of `function __f(): ... {}`, which takes type predicates too
+fn parse_return_type(type_text: &str) -> Option<(String, Tree)> {
+    let source_code = format!("function __f(): {} {{}}", type_text);
+    let tree = parse_type(&source_code)?;
+    Some((source_code, tree))
+}
+
+// The type, type predicate or `asserts` in the return type of the parsed function
+fn return_type(tree: &Tree) -> Option<Node<'_>> {
+    tree.root_node()
+        .named_child(0)?
+        .child_by_field_name("return_type")?
+        .named_child(0)
+}
+
+// Collapse whitespace (including newlines) into single spaces between the tokens of the type, and
+// none inside brackets or before commas, leaving string literals as they are
+fn normalize(type_text: &str) -> String {
+    let Some((source_code, tree)) = parse_return_type(type_text) else {
+        return type_text.split_whitespace().collect::<Vec<_>>().join(" ");
+    };
+    let Some(node) = return_type(&tree) else {
+        return type_text.split_whitespace().collect::<Vec<_>>().join(" ");
+    };
+
+    let mut tokens = Vec::new();
+    collect_tokens(node, &mut tokens);
+    let text = |node: &Node| node.utf8_text(source_code.as_bytes()).unwrap();
+
+    let mut normalized = String::new();
+    let mut previous: Option<Node> = None;
+    for token in tokens {
+        if let Some(previous) = previous {
+            let spaced = previous.end_byte() < token.start_byte();
+            let tight = matches!(text(&previous), "<" | "(" | "[")
+                || matches!(text(&token), ">" | ")" | "]" | ",");
+            if spaced && !tight {
+                normalized.push(' ');
+            }
+        }
+        normalized.push_str(text(&token));
+        previous = Some(token);
+    }
+    normalized
+}
+
+// The leaves of a type in order, with string and template literals kept whole
+fn collect_tokens<'a>(node: Node<'a>, tokens: &mut Vec<Node<'a>>) {
+    if node.child_count() == 0 || matches!(node.kind(), "string" | "template_literal_type") {
+        tokens.push(node);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_tokens(child, tokens);
+    }
+}
+
+fn translate(type_text: &str, style: TypeStyle) -> Option<String> {
+    let (source_code, tree) = parse_return_type(type_text)?;
+    let node = return_type(&tree)?;
+    match node.kind() {
+        // Type predicates only show up as return types
+        "type_predicate" => Some("boolean".to_owned()),
+        "asserts" => Some("void".to_owned()),
+        _ => print(&source_code, &node, style),
+    }
+}
+
+// Print a type node in the Closure style, or None when it has no equivalent
+fn print(source_code: &str, node: &Node, style: TypeStyle) -> Option<String> {
+    let text = node.utf8_text(source_code.as_bytes()).unwrap();
+    let print_child = |index: usize| print(source_code, &node.named_child(index)?, style);
+
+    match node.kind() {
+        "predefined_type" => match text {
+            "any" => Some("*".to_owned()),
+            "unknown" if style == TypeStyle::Closure => Some("?".to_owned()),
+            "unknown" => Some("*".to_owned()),
+            "object" => Some("Object".to_owned()),
+            "never" => None,
+            _ => Some(text.to_owned()),
+        },
+        "type_identifier" | "nested_type_identifier" => Some(text.to_owned()),
+        "existential_type" => Some("*".to_owned()),
+        "readonly_type" | "parenthesized_type" => print_child(0),
+        "array_type" => Some(format!("Array<{}>", print_child(0)?)),
+        "tuple_type" => Some("Array".to_owned()),
+        "generic_type" => {
+            let name = node
+                .child_by_field_name("name")?
+                .utf8_text(source_code.as_bytes())
+                .unwrap();
+            let type_arguments = node.child_by_field_name("type_arguments")?;
+            let mut cursor = type_arguments.walk();
+            let arguments = type_arguments
+                .named_children(&mut cursor)
+                .map(|argument| print(source_code, &argument, style))
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("{}<{}>", name, arguments.join(", ")))
+        }
+        "literal_type" => match node.named_child(0)?.kind() {
+            "string" => Some("string".to_owned()),
+            "number" | "unary_expression" => Some("number".to_owned()),
+            "true" | "false" => Some("boolean".to_owned()),
+            "null" => Some("null".to_owned()),
+            "undefined" => Some("undefined".to_owned()),
+            _ => None,
+        },
+        "union_type" => {
+            let mut members = Vec::new();
+            collect_union(node, &mut members);
+            let mut printed: Vec<String> = Vec::new();
+            for member in members {
+                let member = print(source_code, &member, style)?;
+                if !printed.contains(&member) {
+                    printed.push(member);
+                }
+            }
+
+            let nullable = printed.iter().any(|member| member == "null");
+            let others = printed
+                .iter()
+                .filter(|member| *member != "null")
+                .collect::<Vec<_>>();
+            match (nullable, others.as_slice()) {
+                (_, []) => Some("null".to_owned()),
+                (true, [only]) => Some(format!("?{}", only)),
+                (false, [only]) => Some(only.to_string()),
+                _ => Some(format!("({})", printed.join("|"))),
+            }
+        }
+        "function_type" => {
+            let parameters = node.child_by_field_name("parameters")?;
+            let return_type = match node.child_by_field_name("return_type") {
+                Some(return_type) => print(source_code, &return_type, style)?,
+                None => "*".to_owned(),
+            };
+            Some(format!(
+                "function({}): {}",
+                print_parameters(source_code, &parameters, style)?,
+                return_type
+            ))
+        }
+        "object_type" => print_object(source_code, node, style),
+        _ => None,
+    }
+}
+
+// Flatten `A | B | C`, which parses as nested unions
+fn collect_union<'a>(node: &Node<'a>, members: &mut Vec<Node<'a>>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "union_type" {
+            collect_union(&child, members);
+        } else {
+            members.push(child);
+        }
+    }
+}
+
+// Print function parameters as `string, number=, ...boolean`
+fn print_parameters(source_code: &str, parameters: &Node, style: TypeStyle) -> Option<String> {
+    let mut cursor = parameters.walk();
+    let printed = parameters
+        .named_children(&mut cursor)
+        .filter(|param| param.kind() != "comment")
+        .map(|param| {
+            let param_type = match param
+                .child_by_field_name("type")
+                .and_then(|t| t.named_child(0))
+            {
+                Some(param_type) => print(source_code, &param_type, style)?,
+                None => "*".to_owned(),
+            };
+            let is_rest = param
+                .child_by_field_name("pattern")
+                .is_some_and(|pattern| pattern.kind() == "rest_pattern");
+
+            Some(if is_rest {
+                format!("...{}", param_type)
+            } else if param.kind() == "optional_parameter" {
+                format!("{}=", param_type)
+            } else {
+                param_type
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(printed.join(", "))
+}
+
+// Print an object type as a record `{a: string, b: (number|undefined)}`
+fn print_object(source_code: &str, node: &Node, style: TypeStyle) -> Option<String> {
+    let mut cursor = node.walk();
+    let members = node
+        .named_children(&mut cursor)
+        .filter(|member| member.kind() != "comment" && !member.is_extra())
+        .collect::<Vec<_>>();
+
+    // A lone index signature `{ [key: string]: T }` is a map
+    if let [signature] = members.as_slice() {
+        if signature.kind() == "index_signature" {
+            let key = print(
+                source_code,
+                &signature.child_by_field_name("index_type")?,
+                style,
+            )?;
+            let value = print(
+                source_code,
+                &signature.child_by_field_name("type")?.named_child(0)?,
+                style,
+            )?;
+            return Some(format!("Object<{}, {}>", key, value));
+        }
+    }
+
+    let fields = members
+        .iter()
+        .map(|member| {
+            let name = member
+                .child_by_field_name("name")?
+                .utf8_text(source_code.as_bytes())
+                .unwrap();
+            let field_type = match member.kind() {
+                "property_signature" => {
+                    let mut cursor = member.walk();
+                    let optional = member
+                        .children(&mut cursor)
+                        .any(|child| child.kind() == "?");
+                    let field_type = match member
+                        .child_by_field_name("type")
+                        .and_then(|t| t.named_child(0))
+                    {
+                        Some(field_type) => print(source_code, &field_type, style)?,
+                        None => "*".to_owned(),
+                    };
+                    if optional {
+                        format!("({}|undefined)", field_type)
+                    } else {
+                        field_type
+                    }
+                }
+                "method_signature" => {
+                    let return_type = match member
+                        .child_by_field_name("return_type")
+                        .and_then(|t| t.named_child(0))
+                    {
+                        Some(return_type) => print(source_code, &return_type, style)?,
+                        None => "*".to_owned(),
+                    };
+                    format!(
+                        "function({}): {}",
+                        print_parameters(
+                            source_code,
+                            &member.child_by_field_name("parameters")?,
+                            style
+                        )?,
+                        return_type
+                    )
+                }
+                _ => return None,
+            };
+            Some(format!("{}: {}", name, field_type))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(format!("{{{}}}", fields.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            format_type("Map<\n  string,\n  number\n>", TypeStyle::Typescript),
+            "Map<string, number>"
+        );
+        assert_eq!(
+            format_type(
+                "{\n    a: string;\n    b: number;\n}",
+                TypeStyle::Typescript
+            ),
+            "{ a: string; b: number; }"
+        );
+        assert_eq!(
+            format_type("Map< string , \"a ( b ,c\" >", TypeStyle::Typescript),
+            "Map<string, \"a ( b ,c\">"
+        );
+        assert_eq!(
+            format_type("`a ${ string }  b`", TypeStyle::Typescript),
+            "`a ${ string }  b`"
+        );
+        assert_eq!(
+            format_type("value  is\n string", TypeStyle::Typescript),
+            "value is string"
+        );
+    }
+
+    #[test]
+    fn test_closure() {
+        let closure = |type_text| format_type(type_text, TypeStyle::Closure);
+
+        assert_eq!(closure("string[]"), "Array<string>");
+        assert_eq!(closure("readonly string[]"), "Array<string>");
+        assert_eq!(closure("string | null"), "?string");
+        assert_eq!(closure("string | number"), "(string|number)");
+        assert_eq!(closure("any"), "*");
+        assert_eq!(closure("unknown"), "?");
+        assert_eq!(closure("\"a\" | \"b\""), "string");
+        assert_eq!(closure("Promise<User[]>"), "Promise<Array<User>>");
+        assert_eq!(
+            closure("(err: Error | null, value?: string) => void"),
+            "function(?Error, string=): void"
+        );
+        assert_eq!(
+            closure("{ a: string; b?: number }"),
+            "{a: string, b: (number|undefined)}"
+        );
+        assert_eq!(
+            closure("{ [key: string]: number }"),
+            "Object<string, number>"
+        );
+        assert_eq!(closure("value is string"), "boolean");
+        assert_eq!(closure("this is Foo"), "boolean");
+        assert_eq!(closure("asserts value is string"), "void");
+        assert_eq!(closure("\"a is b\""), "string");
+    }
+
+    #[test]
+    fn test_rewrite_references() {
+        let rewritten = rewrite_references("Map<Id, m.User[]> | Id", |name, whole| {
+            assert!(!whole);
+            (name != "Map").then(|| format!("<{}>", name))
+        });
+        assert_eq!(rewritten, "Map<<Id>, <m.User>[]> | <Id>");
+
+        let rewritten = rewrite_references("Id", |_, whole| whole.then(|| "string".to_owned()));
+        assert_eq!(rewritten, "string");
+    }
+
+    #[test]
+    fn test_untranslatable() {
+        let closure = |type_text| format_type(type_text, TypeStyle::Closure);
+
+        assert_eq!(closure("keyof T"), "*");
+        assert_eq!(closure("T extends string ? A : B"), "*");
+        assert_eq!(closure("{ [K in keyof T]: T[K] }"), "*");
+        assert_eq!(closure("A & B"), "*");
+        assert_eq!(format_type("unknown", TypeStyle::JsdocStrict), "*");
+    }
+}
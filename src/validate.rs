@@ -0,0 +1,524 @@
+use std::ops::Range;
+use tree_sitter::{Node, Tree};
+
+use crate::blocks::{parse_block, DocParam};
+use crate::diagnostics::{
+    Diagnostic, MISSING_PARAM, OPTIONAL_MISMATCH, PARAM_ORDER, TYPE_MISMATCH, UNKNOWN_PARAM,
+    VOID_RETURNS,
+};
+use crate::documenter::Documentation;
+use crate::edits::Edit;
+use crate::infer;
+use crate::structs::FunctionDoc;
+use crate::types::{format_type, TypeStyle};
+
+// A parameter as declared in the signature
+#[derive(Debug, Clone)]
+struct SignatureParam {
+    // None for destructured parameters
+    name: Option<String>,
+    param_type: Option<String>,
+    optional: bool,
+    default: Option<String>,
+    rest: bool,
+    range: Range<usize>,
+}
+
+// Check the existing JsDoc blocks of the documented functions against their signatures
+pub(crate) fn validate_tree(
+    source_code: &str,
+    tree: &Tree,
+    documentation: &Documentation,
+) -> Vec<Diagnostic> {
+    let root_node = tree.root_node();
+    documentation
+        .functions
+        .iter()
+        .filter_map(|function| {
+            let doc = function.existing_doc.as_ref()?;
+            let node = declaration(&root_node, &function.range)?;
+            Some(validate_function(source_code, &node, function, doc))
+        })
+        .flatten()
+        .collect()
+}
+
+// The function or method declared in the range, looking through `export`
+fn declaration<'a>(root_node: &Node<'a>, range: &Range<usize>) -> Option<Node<'a>> {
+    let node = root_node.descendant_for_byte_range(range.start, range.end)?;
+    match node.kind() {
+        "export_statement" => node.child_by_field_name("declaration"),
+        _ => Some(node),
+    }
+}
+
+fn validate_function(
+    source_code: &str,
+    node: &Node,
+    function: &FunctionDoc,
+    doc: &Range<usize>,
+) -> Vec<Diagnostic> {
+    let block = parse_block(source_code, doc.clone());
+    // What would be generated, for the types as the options write them, the existing block when it
+    // is already what would be written
+    let generated = Edit::merge(source_code, &function.edits)
+        .map(|edit| {
+            // Any `@callback` blocks come first
+            let start = edit.replacement.rfind("/**").unwrap_or(0);
+            parse_block(
+                &edit.replacement[start..],
+                0..edit.replacement.len() - start,
+            )
+        })
+        .unwrap_or_else(|| block.clone());
+    let signature = signature_params(source_code, node);
+
+    let name = &function.name;
+    let mut diagnostics = Vec::new();
+    let problem = |rule, range: &Range<usize>, message: String, suggestion: String| {
+        Diagnostic::with_suggestion(source_code, rule, name, range.clone(), message, suggestion)
+    };
+
+    let named = |param_name: &str| {
+        signature
+            .iter()
+            .position(|param| param.name.as_deref() == Some(param_name))
+    };
+    // Properties of object params (`options.name`) are left alone
+    let top_level = block
+        .params
+        .iter()
+        .filter(|param| !param.name.contains('.'))
+        .collect::<Vec<_>>();
+
+    // Names that aren't in the signature, which can't be told apart when some params are destructured
+    let destructured = signature.iter().any(|param| param.name.is_none());
+    let unknown = top_level
+        .iter()
+        .filter(|param| named(&param.name).is_none() && !destructured)
+        .collect::<Vec<_>>();
+    let missing = function
+        .params
+        .iter()
+        .filter(|param| !param.name.contains('.'))
+        .filter(|param| !top_level.iter().any(|doc| doc.name == param.name))
+        .collect::<Vec<_>>();
+    for (i, param) in unknown.iter().enumerate() {
+        let (message, suggestion) = match missing.get(i) {
+            Some(replacement) => (
+                format!("`@param {}` isn't a parameter of `{}`", param.name, name),
+                format!("Rename it to `{}`", replacement.name),
+            ),
+            None => (
+                format!("`@param {}` isn't a parameter of `{}`", param.name, name),
+                "Remove the tag".to_owned(),
+            ),
+        };
+        diagnostics.push(problem(UNKNOWN_PARAM, &param.range, message, suggestion));
+    }
+    for param in missing.iter().skip(unknown.len()) {
+        let tag = generated
+            .params
+            .iter()
+            .find(|generated| generated.name == param.name)
+            .map_or(format!("@param {}", param.name), |generated| {
+                param_tag(generated)
+            });
+        let range = named(&param.name).map_or(doc.clone(), |i| signature[i].range.clone());
+        diagnostics.push(problem(
+            MISSING_PARAM,
+            &range,
+            format!("`@param {}` is missing for `{}`", param.name, name),
+            format!("Add `{}`", tag),
+        ));
+    }
+
+    // The documented params should be in the order of the signature
+    let known = top_level
+        .iter()
+        .filter_map(|param| Some((named(&param.name)?, *param)))
+        .collect::<Vec<_>>();
+    if let Some(pair) = known.windows(2).find(|pair| pair[0].0 > pair[1].0) {
+        let mut order = known.clone();
+        order.sort_by_key(|(i, _)| *i);
+        let order = order
+            .iter()
+            .map(|(_, param)| param.name.as_str())
+            .collect::<Vec<_>>();
+        diagnostics.push(problem(
+            PARAM_ORDER,
+            &pair[1].1.range,
+            format!(
+                "`@param {}` should come before `@param {}`",
+                pair[1].1.name, pair[0].1.name
+            ),
+            format!(
+                "Order the `@param` tags as in the signature: {}",
+                order.join(", ")
+            ),
+        ));
+    }
+
+    for (i, param) in known {
+        let declared = &signature[i];
+        let expected = generated
+            .params
+            .iter()
+            .find(|generated| generated.name == param.name)
+            .and_then(|generated| generated.param_type.as_deref());
+        if let (Some(doc_type), Some(declared_type)) = (&param.param_type, &declared.param_type) {
+            if !declared.rest && !same_type(doc_type, declared_type, expected) {
+                diagnostics.push(problem(
+                    TYPE_MISMATCH,
+                    &param.range,
+                    format!(
+                        "`@param {}` has the type `{}` but is declared as `{}`",
+                        param.name, doc_type, declared_type
+                    ),
+                    format!(
+                        "Change it to `{{{}}}`",
+                        expected.unwrap_or(&format_type(declared_type, TypeStyle::Typescript))
+                    ),
+                ));
+            }
+        }
+
+        if let Some((message, suggestion)) = optional_mismatch(param, declared) {
+            diagnostics.push(problem(
+                OPTIONAL_MISMATCH,
+                &param.range,
+                message,
+                suggestion,
+            ));
+        }
+    }
+
+    if let Some(returns) = &block.returns {
+        let declared = node.child_by_field_name("return_type").map(|return_type| {
+            let text = return_type.utf8_text(source_code.as_bytes()).unwrap();
+            text.trim_start_matches(':').trim().to_owned()
+        });
+        let returns_nothing = match &declared {
+            Some(declared) => declared == "void",
+            None => {
+                !infer::is_generator(node)
+                    && infer::infer_types(source_code, node).returns.as_deref() == Some("void")
+            }
+        };
+        let doc_type = returns.return_type.as_deref();
+        if returns_nothing && !matches!(doc_type, Some("void" | "undefined")) {
+            diagnostics.push(problem(
+                VOID_RETURNS,
+                &returns.range,
+                format!("`{}` doesn't return anything", name),
+                "Remove the `@returns` tag".to_owned(),
+            ));
+        } else if let (Some(doc_type), Some(declared)) = (doc_type, &declared) {
+            let expected = generated
+                .returns
+                .as_ref()
+                .and_then(|returns| returns.return_type.as_deref());
+            if !infer::is_generator(node) && !same_type(doc_type, declared, expected) {
+                diagnostics.push(problem(
+                    TYPE_MISMATCH,
+                    &returns.range,
+                    format!(
+                        "`@returns` has the type `{}` but `{}` returns `{}`",
+                        doc_type, name, declared
+                    ),
+                    format!(
+                        "Change it to `{{{}}}`",
+                        expected.unwrap_or(&format_type(declared, TypeStyle::Typescript))
+                    ),
+                ));
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+    diagnostics
+}
+
+// Checks a documented type against the declared one, in any of the styles or as it would be generated
+fn same_type(doc_type: &str, declared: &str, generated: Option<&str>) -> bool {
+    let doc_type = format_type(doc_type, TypeStyle::Typescript);
+    generated == Some(doc_type.as_str())
+        || [
+            TypeStyle::Typescript,
+            TypeStyle::Closure,
+            TypeStyle::JsdocStrict,
+        ]
+        .iter()
+        .any(|style| format_type(declared, *style) == doc_type)
+}
+
+// Brackets for params that are optional in the signature and defaults that agree with it. Params with
+// a default can be written either way
+fn optional_mismatch(param: &DocParam, declared: &SignatureParam) -> Option<(String, String)> {
+    let with_brackets = |default: Option<&String>| match default {
+        Some(default) => format!("[{}={}]", param.name, default),
+        None => format!("[{}]", param.name),
+    };
+
+    if let (Some(documented), Some(default)) = (&param.default, &declared.default) {
+        if unquote(documented) != unquote(default) {
+            return Some((
+                format!(
+                    "`@param {}` says it defaults to `{}` but the default is `{}`",
+                    param.name, documented, default
+                ),
+                format!("Write it as `{}`", with_brackets(Some(default))),
+            ));
+        }
+    }
+
+    if param.optional && !declared.optional && !declared.rest {
+        Some((
+            format!(
+                "`@param {}` is optional but the parameter is required",
+                param.name
+            ),
+            format!("Write it as `{}` without the brackets", param.name),
+        ))
+    } else if !param.optional && declared.optional && declared.default.is_none() {
+        Some((
+            format!(
+                "`@param {}` is required but the parameter is optional",
+                param.name
+            ),
+            format!("Write it as `{}`", with_brackets(None)),
+        ))
+    } else {
+        None
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+}
+
+// Write a tag back out for a suggestion
+fn param_tag(param: &DocParam) -> String {
+    let name = match (&param.default, param.optional) {
+        (Some(default), true) => format!("[{}={}]", param.name, default),
+        (None, true) => format!("[{}]", param.name),
+        (Some(default), false) => format!("{}={}", param.name, default),
+        (None, false) => param.name.clone(),
+    };
+    match &param.param_type {
+        Some(param_type) => format!("@param {{{}}} {}", param_type, name),
+        None => format!("@param {}", name),
+    }
+}
+
+fn signature_params(source_code: &str, node: &Node) -> Vec<SignatureParam> {
+    let Some(parameters) = node.child_by_field_name("parameters") else {
+        return Vec::new();
+    };
+    let text = |node: Node| node.utf8_text(source_code.as_bytes()).unwrap().to_owned();
+
+    let mut cursor = parameters.walk();
+    let params = parameters
+        .named_children(&mut cursor)
+        .filter(|param| {
+            param.kind() == "required_parameter" || param.kind() == "optional_parameter"
+        })
+        .filter_map(|param| {
+            let pattern = param.child_by_field_name("pattern")?;
+            let (name, rest) = match pattern.kind() {
+                // Documented with `@this` rather than `@param`
+                "this" => return None,
+                "identifier" => (Some(text(pattern)), false),
+                "rest_pattern" => (
+                    pattern
+                        .named_child(0)
+                        .filter(|name| name.kind() == "identifier")
+                        .map(text),
+                    true,
+                ),
+                _ => (None, false),
+            };
+            let default = param.child_by_field_name("value").map(text);
+            Some(SignatureParam {
+                name,
+                param_type: param
+                    .child_by_field_name("type")
+                    .and_then(|annotation| annotation.named_child(0))
+                    .map(|type_node| crate::type_text(source_code, &type_node)),
+                optional: param.kind() == "optional_parameter" || default.is_some(),
+                default,
+                rest,
+                range: param.byte_range(),
+            })
+        })
+        .collect();
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Documenter, Options};
+
+    fn validate(source_code: &str) -> Vec<(&'static str, String, String)> {
+        Documenter::new()
+            .options(Options::default())
+            .validate(source_code)
+            .into_iter()
+            .map(|d| (d.rule, d.message, d.suggestion.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_unknown_and_missing() {
+        // As in `test_support_existing_doc_block_outdated`
+        let source_code = "/**\n * my outdated doc block\n *\n * @param old {string}\n */\nfunction a(new: string) {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![(
+                UNKNOWN_PARAM,
+                "`@param old` isn't a parameter of `a`".to_owned(),
+                "Rename it to `new`".to_owned()
+            )]
+        );
+        let diagnostic = &Documenter::new().validate(source_code)[0];
+        assert_eq!(
+            &source_code[diagnostic.range.clone()],
+            "@param old {string}"
+        );
+
+        let source_code = "/**\n * @param {string} a\n * @param {string} z\n */\nfunction a(a: string, b?: number) {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![(
+                UNKNOWN_PARAM,
+                "`@param z` isn't a parameter of `a`".to_owned(),
+                "Rename it to `b`".to_owned()
+            )]
+        );
+
+        let source_code = "/**\n * @param {string} a\n * @param {string} z\n */\nfunction a(a: string, ...c: string[]) {}\n/**\n * @param {string} a\n */\nfunction b(a: string, b?: number) {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![
+                (
+                    UNKNOWN_PARAM,
+                    "`@param z` isn't a parameter of `a`".to_owned(),
+                    "Remove the tag".to_owned()
+                ),
+                (
+                    MISSING_PARAM,
+                    "`@param b` is missing for `b`".to_owned(),
+                    "Add `@param {number} [b]`".to_owned()
+                ),
+            ]
+        );
+
+        // Destructured params can go by any name, and properties are left alone
+        let source_code = "/**\n * @param {Options} options\n * @param {string} options.name\n */\nfunction a({ name }: Options) {}\n";
+        assert!(validate(source_code).is_empty());
+    }
+
+    #[test]
+    fn test_order() {
+        let source_code = "/**\n * @param {number} b\n * @param {string} a\n */\nfunction f(a: string, b: number) {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![(
+                PARAM_ORDER,
+                "`@param a` should come before `@param b`".to_owned(),
+                "Order the `@param` tags as in the signature: a, b".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_types() {
+        let source_code = "/**\n * @param {number} a\n * @param {Array<string>} b\n * @param {?string} c\n * @returns {string}\n */\nfunction f(a: string, b: readonly string[], c: string | null): number {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![
+                (
+                    TYPE_MISMATCH,
+                    "`@param a` has the type `number` but is declared as `string`".to_owned(),
+                    "Change it to `{string}`".to_owned()
+                ),
+                (
+                    TYPE_MISMATCH,
+                    "`@returns` has the type `string` but `f` returns `number`".to_owned(),
+                    "Change it to `{number}`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_void_returns() {
+        let source_code = "/**\n * @returns {string}\n */\nfunction f() {}\n/**\n * @returns {number}\n */\nfunction g(): void {}\n/**\n * @returns {void}\n */\nfunction h() {}\n/**\n * @returns {number}\n */\nfunction i() { return 1 }\n";
+        assert_eq!(
+            validate(source_code),
+            vec![
+                (
+                    VOID_RETURNS,
+                    "`f` doesn't return anything".to_owned(),
+                    "Remove the `@returns` tag".to_owned()
+                ),
+                (
+                    VOID_RETURNS,
+                    "`g` doesn't return anything".to_owned(),
+                    "Remove the `@returns` tag".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional() {
+        let source_code = "/**\n * @param {string} [a]\n * @param {number} b\n * @param {number} [c=2]\n * @param {string} [d]\n * @param {string} e=\"x\"\n */\nfunction f(a: string, b?: number, c = 1, d = \"y\", e: string = \"x\") {}\n";
+        assert_eq!(
+            validate(source_code),
+            vec![
+                (
+                    OPTIONAL_MISMATCH,
+                    "`@param a` is optional but the parameter is required".to_owned(),
+                    "Write it as `a` without the brackets".to_owned()
+                ),
+                (
+                    OPTIONAL_MISMATCH,
+                    "`@param b` is required but the parameter is optional".to_owned(),
+                    "Write it as `[b]`".to_owned()
+                ),
+                (
+                    OPTIONAL_MISMATCH,
+                    "`@param c` says it defaults to `2` but the default is `1`".to_owned(),
+                    "Write it as `[c=1]`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generated_blocks_are_valid() {
+        let source_code = "function f(a: string, b?: number, c = 5, d: string = \"x\", ...e: number[]): void {}\nclass A {\n  g(h: (err: Error) => void): Promise<string> {}\n}\n";
+        for options in [
+            Options::default(),
+            Options {
+                infer_returns: true,
+                type_style: TypeStyle::Closure,
+                callback_typedefs: true,
+                ..Options::default()
+            },
+        ] {
+            let documenter = Documenter::new().options(options);
+            let documented = documenter.process(source_code);
+            assert_eq!(
+                documenter.validate(&documented),
+                Vec::new(),
+                "{}",
+                documented
+            );
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::request::{process_source, ProcessOptions};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+export interface Options {
+    exportedOnly?: boolean;
+    publicOnly?: boolean;
+    skipConstructors?: boolean;
+    minLines?: number;
+    ignorePattern?: string;
+    description?: "name" | "humanize" | "todo";
+    verbRules?: [verb: string, template: string][];
+    stripParamComments?: boolean;
+    inferReturns?: boolean;
+    skipVoidReturns?: boolean;
+    inferThrows?: boolean;
+    callbackTypedefs?: boolean;
+    typeStyle?: "typescript" | "closure" | "jsdoc-strict";
+    typeReferences?: "keep" | "inline" | "see" | "import";
+    /** Also report the problems with the existing blocks in the diagnostics */
+    validate?: boolean;
+}
+
+/** A zero based line and column, with the column in UTF-16 code units */
+export interface Position {
+    line: number;
+    column: number;
+}
+
+export interface Diagnostic {
+    rule:
+        | "auto-js-doc/missing-jsdoc"
+        | "auto-js-doc/outdated-jsdoc"
+        | "auto-js-doc/unknown-param"
+        | "auto-js-doc/missing-param"
+        | "auto-js-doc/param-order"
+        | "auto-js-doc/type-mismatch"
+        | "auto-js-doc/void-returns"
+        | "auto-js-doc/optional-mismatch";
+    message: string;
+    /** How to fix it, for the problems found with `validate` */
+    suggestion?: string;
+    name: string;
+    range: { start: number; end: number };
+    start: Position;
+    end: Position;
+}
+
+/** An edit to the source as given, with its range as bytes and as lines and columns */
+export interface Edit {
+    range: { start: number; end: number };
+    start: Position;
+    end: Position;
+    replacement: string;
+}
+
+export interface ProcessResult {
+    source: string;
+    edits: Edit[];
+    diagnostics: Diagnostic[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Options")]
+    pub type JsOptions;
+
+    #[wasm_bindgen(typescript_type = "ProcessResult")]
+    pub type JsProcessResult;
+}
+
+/// Add JsDoc blocks to the functions in the source, returning the new source, its edits and what was missing or outdated
+#[wasm_bindgen]
+pub fn process(source: &str, options: Option<JsOptions>) -> Result<JsProcessResult, JsError> {
+    let result = process_source(source, parse_options(options)?);
+
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    let value = result
+        .serialize(&serializer)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(value.unchecked_into())
+}
+
+// Read the options object, using the defaults for anything left out
+fn parse_options(options: Option<JsOptions>) -> Result<ProcessOptions, JsError> {
+    match options {
+        Some(options) => serde_wasm_bindgen::from_value(options.into())
+            .map_err(|e| JsError::new(&format!("Invalid options: {}", e))),
+        None => Ok(ProcessOptions::default()),
+    }
+}
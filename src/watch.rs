@@ -0,0 +1,165 @@
+use auto_js_doc::{apply_edits, Documenter, Options};
+use notify::{EventKind, RecursiveMode, Watcher as _};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+
+use crate::files::is_source;
+
+// How long to wait for a burst of writes (an editor saving or a formatter running) to end
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Document the `.ts` and `.js` files under the directory whenever they are saved
+pub fn run_watch(dir: &Path, options: &Options) -> Result<(), Box<dyn Error + Sync + Send>> {
+    // Events come with the watched path as the prefix
+    let dir = dir.canonicalize()?;
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&dir, RecursiveMode::Recursive)?;
+    eprintln!("Watching {}", dir.display());
+
+    let mut files = Files::new(options.clone());
+    while let Some(paths) = next_batch(&dir, &receiver) {
+        for path in paths {
+            match files.update(&path) {
+                Ok(true) => eprintln!("Documented {}", path.display()),
+                Ok(false) => {}
+                Err(e) => eprintln!("Error updating {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Wait for a change, then collect the paths changed until things are quiet, none when the watcher is gone
+fn next_batch(
+    dir: &Path,
+    receiver: &Receiver<notify::Result<notify::Event>>,
+) -> Option<BTreeSet<PathBuf>> {
+    let mut paths = BTreeSet::new();
+    let mut event = receiver.recv().ok()?;
+    loop {
+        match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                paths.extend(event.paths.into_iter().filter(|path| is_source(dir, path)));
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Error watching: {}", e),
+        }
+        match receiver.recv_timeout(DEBOUNCE) {
+            Ok(next) => event = next,
+            Err(_) => return Some(paths),
+        }
+    }
+}
+
+// The files written, to tell our own writes from the ones to document
+struct Files {
+    options: Options,
+    written: HashMap<PathBuf, String>,
+}
+
+impl Files {
+    fn new(options: Options) -> Files {
+        Files {
+            options,
+            written: HashMap::new(),
+        }
+    }
+
+    // Add the missing blocks to a file, returning whether it changed
+    fn update(&mut self, path: &Path) -> io::Result<bool> {
+        let source_code = match fs::read_to_string(path) {
+            Ok(source_code) => source_code,
+            // Removed or renamed since the event
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        if self.written.get(path) == Some(&source_code) {
+            return Ok(false);
+        }
+
+        let mut options = self.options.clone();
+        options.source_path = Some(path.to_owned());
+        let documenter = Documenter::new().options(options);
+        // Rewriting the blocks already there under the editor would fight the user as they type
+        let edits = documenter
+            .document(&source_code)
+            .functions
+            .into_iter()
+            .filter(|function| function.existing_doc.is_none())
+            .flat_map(|function| function.edits)
+            .collect::<Vec<_>>();
+
+        let updated = apply_edits(&source_code, &edits);
+        if updated == source_code {
+            return Ok(false);
+        }
+        fs::write(path, &updated)?;
+        self.written.insert(path.to_owned(), updated);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let dir = std::env::temp_dir().join(format!("auto-js-doc-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.ts");
+        fs::write(&path, "function a() {}\n").unwrap();
+
+        let mut files = Files::new(Options::default());
+        assert!(files.update(&path).unwrap());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "/**\n * a\n */\nfunction a() {}\n"
+        );
+
+        // The event for our own write
+        assert!(!files.update(&path).unwrap());
+
+        fs::write(&path, "/**\n * a\n */\nfunction a() {}\nfunction b() {}\n").unwrap();
+        assert!(files.update(&path).unwrap());
+        assert!(!files.update(&dir.join("missing.ts")).unwrap());
+
+        // An outdated block is left to the user
+        let outdated = "/**\n * a\n */\nfunction a(b: string) {}\n";
+        fs::write(&path, outdated).unwrap();
+        assert!(!files.update(&path).unwrap());
+        let unchanged = fs::read_to_string(&path).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(unchanged, outdated);
+    }
+
+    #[test]
+    fn test_next_batch() {
+        let (sender, receiver) = channel();
+        let event = |path: &str| {
+            Ok(
+                notify::Event::new(EventKind::Modify(notify::event::ModifyKind::Any))
+                    .add_path(PathBuf::from(path)),
+            )
+        };
+        sender.send(event("a.ts")).unwrap();
+        sender.send(event("a.ts")).unwrap();
+        sender.send(event("b.rs")).unwrap();
+        sender.send(event("c.js")).unwrap();
+        drop(sender);
+
+        let paths = next_batch(Path::new(""), &receiver).unwrap();
+        assert_eq!(
+            paths.into_iter().collect::<Vec<_>>(),
+            [PathBuf::from("a.ts"), PathBuf::from("c.js")]
+        );
+        assert!(next_batch(Path::new(""), &receiver).is_none());
+    }
+}